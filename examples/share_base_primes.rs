@@ -0,0 +1,99 @@
+// Compares the two ways of handing the base primes to the slices of the P17 sieve:
+// the old one cloning the whole bitmap for every slice and scanning it for the set bits,
+// the current one sharing a precomputed list of the primes.
+//
+//     cargo run --release --example share_base_primes -- 1e9 16 4096
+
+use parallel_primes::harness::Stats;
+use parallel_primes::pool::Executor;
+use parallel_primes::sieve::packed_sieve;
+use parallel_primes::PackedBits;
+use std::env;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 5;
+
+// sieves start..end with base_primes, like recursive_primes_p17(), and counts the primes
+fn sieve_slice(base_primes: impl Iterator<Item = usize>, start: usize, end: usize) -> u64 {
+    let mut v: PackedBits = PackedBits::new_set(end - start, true);
+    for p in base_primes {
+        let j = start.div_ceil(p) * p;
+        for i in (j..end).step_by(p) {
+            v.clear(i - start);
+        }
+    }
+    v.count_ones(v.len()) as u64
+}
+
+// returns the number of primes in small_primes.len()..max and the time spent preparing the
+// base primes of the slices
+fn run(max: usize, threads: usize, page_size: usize, share: bool) -> (u64, Duration) {
+    let small_primes = packed_sieve((max as f64).sqrt() as u64 + 1);
+    let slice_size = (page_size * 8).max(64);
+    let workers = Executor::Pool.workers(threads);
+    let shared: Arc<Vec<u32>> = Arc::new(small_primes.iter_ones().map(|p| p as u32).collect());
+
+    let mut copy_time = Duration::ZERO;
+    let mut sum = 0;
+    let mut start = small_primes.len();
+    while start < max {
+        let mut tasks = vec![];
+        for _ in 0..threads {
+            if start >= max {
+                break;
+            }
+            let end = (start + slice_size).min(max);
+            let time_start = Instant::now();
+            let task = if share {
+                let base_primes = shared.clone();
+                copy_time += time_start.elapsed();
+                workers.submit(move || {
+                    sieve_slice(base_primes.iter().map(|&p| p as usize), start, end)
+                })
+            } else {
+                let small_primes = small_primes.clone();
+                copy_time += time_start.elapsed();
+                workers.submit(move || sieve_slice(small_primes.iter_ones(), start, end))
+            };
+            tasks.push(task);
+            start = end;
+        }
+        sum += tasks.into_iter().map(|task| task.join()).sum::<u64>();
+    }
+    (sum, copy_time)
+}
+
+fn parse(arg: Option<String>, default: usize) -> usize {
+    arg.map(|s| s.parse::<f64>().expect("not a number") as usize)
+        .unwrap_or(default)
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let max = parse(args.next(), 1_000_000_000);
+    let threads = parse(args.next(), 16);
+    let page_size = parse(args.next(), 4096);
+    println!("max {max}, {threads} threads, page size {page_size}, median of {RUNS} runs");
+
+    let mut counts = vec![];
+    for (name, share) in [("clone bitmap", false), ("shared list", true)] {
+        let mut totals = vec![];
+        let mut copies = vec![];
+        for _ in 0..RUNS {
+            let time_start = Instant::now();
+            let (count, copy_time) = run(max, threads, page_size, share);
+            totals.push(time_start.elapsed());
+            copies.push(copy_time);
+            counts.push(count);
+        }
+        let total = Stats::from_samples(&totals);
+        let copy = Stats::from_samples(&copies);
+        println!(
+            "{name:12}  total {:>10.3?}  handing out base primes {:>10.3?}",
+            total.median, copy.median
+        );
+    }
+    counts.dedup();
+    assert_eq!(counts.len(), 1, "the two ways count differently");
+}
@@ -2,7 +2,7 @@
 
 cargo fmt
 cargo clippy &&
-RUST_BACKTRACE=1 cargo run --release
+RUST_BACKTRACE=1 cargo run --release -- bench --detail
 echo --------------------------------------------------------------------------------
 inotifywait -q -e close_write src Cargo.toml run.sh
 clear
@@ -0,0 +1,392 @@
+//! A minimal unsigned big integer, just enough for the primality tests of numbers beyond `u64`.
+//!
+//! The limbs are `u64`, least significant first, without leading zero limbs, so zero has none.
+//! Products and quotients go through `u128`, division is Knuth's algorithm D.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Rem, Shl, Shr, Sub};
+use std::str::FromStr;
+
+/// An arbitrary-precision unsigned integer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u64>,
+}
+
+/// The error of parsing a [`BigUint`] from anything but decimal digits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseBigUintError;
+
+impl fmt::Display for ParseBigUintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid digit in unsigned integer")
+    }
+}
+
+impl Error for ParseBigUintError {}
+
+// the largest power of ten in a u64, for converting from and to decimal 19 digits at a time
+const TEN_19: u64 = 10_000_000_000_000_000_000;
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: vec![] }
+    }
+
+    pub fn one() -> BigUint {
+        BigUint::from(1u64)
+    }
+
+    // drops the leading zero limbs
+    fn normalized(mut limbs: Vec<u64>) -> BigUint {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.limbs.first().is_none_or(|&low| low & 1 == 0)
+    }
+
+    /// Returns the number of bits without the leading zeros, 0 for zero.
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(&top) => self.limbs.len() as u64 * 64 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// Returns bit `i`, the least significant one is bit 0.
+    pub fn bit(&self, i: u64) -> bool {
+        self.limbs
+            .get((i / 64) as usize)
+            .is_some_and(|&limb| limb >> (i % 64) & 1 == 1)
+    }
+
+    /// Returns the number of trailing zero bits, 0 for zero.
+    pub fn trailing_zeros(&self) -> u64 {
+        match self.limbs.iter().position(|&limb| limb != 0) {
+            Some(idx) => idx as u64 * 64 + self.limbs[idx].trailing_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// Returns the value if it fits in a `u64`.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0]),
+            _ => None,
+        }
+    }
+
+    /// Returns `self % d`, panics if `d` is 0.
+    pub fn rem_u64(&self, d: u64) -> u64 {
+        self.div_rem_u64(d).1
+    }
+
+    // divides by a single limb, panics if d is 0
+    fn div_rem_u64(&self, d: u64) -> (BigUint, u64) {
+        assert!(d != 0, "division by zero");
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut rest = 0u128;
+        for (q, &limb) in quotient.iter_mut().zip(&self.limbs).rev() {
+            let num = rest << 64 | limb as u128;
+            *q = (num / d as u128) as u64;
+            rest = num % d as u128;
+        }
+        (BigUint::normalized(quotient), rest as u64)
+    }
+
+    // self * m + a in place
+    fn mul_add_u64(&mut self, m: u64, a: u64) {
+        let mut carry = a as u128;
+        for limb in &mut self.limbs {
+            let t = *limb as u128 * m as u128 + carry;
+            *limb = t as u64;
+            carry = t >> 64;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u64);
+        }
+    }
+
+    /// Returns the quotient and the remainder, panics if `d` is 0.
+    pub fn div_rem(&self, d: &BigUint) -> (BigUint, BigUint) {
+        assert!(!d.is_zero(), "division by zero");
+        if self < d {
+            return (BigUint::zero(), self.clone());
+        }
+        if d.limbs.len() == 1 {
+            let (q, r) = self.div_rem_u64(d.limbs[0]);
+            return (q, BigUint::from(r));
+        }
+
+        // normalize so the top limb of the divisor has its highest bit set, u gets an extra limb
+        let shift = d.limbs.last().unwrap().leading_zeros() as usize;
+        let v = (d << shift).limbs;
+        let mut u = (self << shift).limbs;
+        u.resize(self.limbs.len() + 1, 0);
+        let n = v.len();
+        let m = u.len() - n;
+        let v_top = v[n - 1] as u128;
+        let v_next = v[n - 2] as u128;
+
+        let mut quotient = vec![0; m];
+        for j in (0..m).rev() {
+            // estimate the quotient digit from the top two limbs, it is at most 2 too large
+            let num = (u[j + n] as u128) << 64 | u[j + n - 1] as u128;
+            let mut q_hat = num / v_top;
+            let mut r_hat = num % v_top;
+            while q_hat >> 64 != 0 || q_hat * v_next > (r_hat << 64 | u[j + n - 2] as u128) {
+                q_hat -= 1;
+                r_hat += v_top;
+                if r_hat >> 64 != 0 {
+                    break;
+                }
+            }
+
+            // u[j..=j + n] -= q_hat * v
+            let mut borrow = 0i128;
+            let mut carry = 0u128;
+            for i in 0..n {
+                let p = q_hat * v[i] as u128 + carry;
+                carry = p >> 64;
+                let t = u[i + j] as i128 - borrow - (p as u64) as i128;
+                u[i + j] = t as u64;
+                borrow = (t < 0) as i128;
+            }
+            let t = u[j + n] as i128 - borrow - carry as i128;
+            u[j + n] = t as u64;
+
+            // the estimate was one too large, add v back
+            if t < 0 {
+                q_hat -= 1;
+                let mut carry = 0u128;
+                for i in 0..n {
+                    let s = u[i + j] as u128 + v[i] as u128 + carry;
+                    u[i + j] = s as u64;
+                    carry = s >> 64;
+                }
+                u[j + n] = u[j + n].wrapping_add(carry as u64);
+            }
+            quotient[j] = q_hat as u64;
+        }
+
+        u.truncate(n);
+        let rest = &BigUint::normalized(u) >> shift;
+        (BigUint::normalized(quotient), rest)
+    }
+
+    /// Returns `self^exp % m`, panics if `m` is 0.
+    pub fn pow_mod(&self, exp: &BigUint, m: &BigUint) -> BigUint {
+        let mut result = &BigUint::one() % m;
+        let mut base = self % m;
+        for i in 0..exp.bits() {
+            if exp.bit(i) {
+                result = &(&result * &base) % m;
+            }
+            base = &(&base * &base) % m;
+        }
+        result
+    }
+
+    /// Returns the largest `r` with `r * r <= self`.
+    pub fn sqrt(&self) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+        // Newton's iteration from above, starting with a power of two larger than the root
+        let mut x = &BigUint::one() << self.bits().div_ceil(2) as usize;
+        loop {
+            let y = &(&x + &(self / &x)) >> 1;
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> BigUint {
+        BigUint::normalized(vec![n])
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> BigUint {
+        BigUint::normalized(vec![n as u64, (n >> 64) as u64])
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = false;
+        for (i, &a) in long.limbs.iter().enumerate() {
+            let (s, c1) = a.overflowing_add(short.limbs.get(i).copied().unwrap_or(0));
+            let (s, c2) = s.overflowing_add(carry as u64);
+            limbs.push(s);
+            carry = c1 || c2;
+        }
+        limbs.push(carry as u64);
+        BigUint::normalized(limbs)
+    }
+}
+
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    /// Panics if `other` is larger than `self`.
+    fn sub(self, other: &BigUint) -> BigUint {
+        assert!(*self >= *other, "subtraction underflow");
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = false;
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let (d, b1) = a.overflowing_sub(other.limbs.get(i).copied().unwrap_or(0));
+            let (d, b2) = d.overflowing_sub(borrow as u64);
+            limbs.push(d);
+            borrow = b1 || b2;
+        }
+        BigUint::normalized(limbs)
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u64; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let t = a as u128 * b as u128 + limbs[i + j] as u128 + carry;
+                limbs[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            limbs[i + other.limbs.len()] = carry as u64;
+        }
+        BigUint::normalized(limbs)
+    }
+}
+
+impl Div for &BigUint {
+    type Output = BigUint;
+
+    fn div(self, other: &BigUint) -> BigUint {
+        self.div_rem(other).0
+    }
+}
+
+impl Rem for &BigUint {
+    type Output = BigUint;
+
+    fn rem(self, other: &BigUint) -> BigUint {
+        self.div_rem(other).1
+    }
+}
+
+impl Shl<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shl(self, shift: usize) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+        let (words, bits) = (shift / 64, shift % 64);
+        let mut limbs = vec![0; words];
+        let mut carry = 0;
+        for &limb in &self.limbs {
+            limbs.push(limb << bits | carry);
+            carry = if bits == 0 { 0 } else { limb >> (64 - bits) };
+        }
+        limbs.push(carry);
+        BigUint::normalized(limbs)
+    }
+}
+
+impl Shr<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shr(self, shift: usize) -> BigUint {
+        let (words, bits) = (shift / 64, shift % 64);
+        let high = self.limbs.get(words..).unwrap_or(&[]);
+        let limbs = (0..high.len())
+            .map(|i| {
+                let next = high.get(i + 1).copied().unwrap_or(0);
+                let carry = if bits == 0 { 0 } else { next << (64 - bits) };
+                high[i] >> bits | carry
+            })
+            .collect();
+        BigUint::normalized(limbs)
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ParseBigUintError;
+
+    /// Parses decimal digits.
+    fn from_str(s: &str) -> Result<BigUint, ParseBigUintError> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigUintError);
+        }
+        let mut n = BigUint::zero();
+        for chunk in s.as_bytes().chunks(19) {
+            // the chunk is ascii digits, so it parses
+            let digits = std::str::from_utf8(chunk).unwrap();
+            let value: u64 = digits.parse().unwrap();
+            n.mul_add_u64(10u64.pow(chunk.len() as u32), value);
+        }
+        Ok(n)
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad("0");
+        }
+        let mut chunks = vec![];
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (q, r) = n.div_rem_u64(TEN_19);
+            chunks.push(r);
+            n = q;
+        }
+        let mut s = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            s += &format!("{:019}", chunk);
+        }
+        f.pad(&s)
+    }
+}
@@ -0,0 +1,223 @@
+use parallel_primes::bigint::BigUint;
+use parallel_primes::pool::Executor;
+use parallel_primes::schedule::Schedule;
+
+pub const USAGE: &str = "\
+usage: parallel_primes <command> [options]
+
+commands:
+  count    run one algorithm once and print the number of primes below --max
+  bench    run the benchmark suite, or a single algorithm with --algo
+  tune     search the fastest --threads and --page-size for --algo and save them
+  is-prime <n>...
+           tell whether each n is prime, with a deterministic Miller-Rabin test up to 2^64 - 1
+           and the Baillie-PSW probable prime test above
+  factor [<n>...]
+           print the prime factors of each n (up to 2^64 - 1), read from stdin without arguments
+  list     list the available algorithms
+  help     print this message
+
+options:
+  --algo <name>        algorithm to run, e.g. p1 .. p20 (see `list`)
+  --max <n>            count primes below n, e.g. 3000000000, 3_000_000_000 or 3e9
+  --threads <n>        number of worker threads
+  --page-size <n>      page size used by the segmented sieves
+  --detail             print the thread/page size grid of the algorithms with both (p12a, p16 - p20)
+  --spawn              start a thread per slice instead of using the thread pool, to compare
+  --schedule <s>       how the counting sieves (p17 - p20) hand out their slices: dynamic
+                       (default, threads take the next slices when done) or static
+  --chunk <n>          slices a thread takes at a time with the dynamic schedule (default: 1)
+  --load               print the busy and idle time of every thread after each run
+  --verify             check the prime count against the built-in table or an independent
+                       algorithm and fail with exit code 1 on a mismatch
+  --warmup <n>         untimed runs before measuring (bench default: 1, count: 0)
+  --runs <n>           timed runs per configuration (bench default: 3, count: 1)
+  --format <format>    text (default), jsonl or csv
+  --output <file>      write the jsonl or csv results to a file instead of stdout
+  --config <file>      tuned parameters, used when --threads or --page-size are not given
+                       (default: $PARALLEL_PRIMES_CONFIG or ~/.config/parallel_primes/tuned.conf)
+";
+
+#[derive(Debug)]
+pub enum Command {
+    Count(Options),
+    Bench(Options),
+    Tune(Options),
+    IsPrime(Vec<BigUint>),
+    // empty to read the numbers from stdin
+    Factor(Vec<u64>),
+    List,
+    Help,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Jsonl,
+    Csv,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub algo: Option<String>,
+    pub max: u64,
+    pub threads: Option<usize>,
+    pub page_size: Option<usize>,
+    pub detail: bool,
+    pub executor: Executor,
+    pub schedule: Schedule,
+    pub load: bool,
+    pub verify: bool,
+    pub warmup: Option<usize>,
+    pub runs: Option<usize>,
+    pub format: Format,
+    pub output: Option<String>,
+    pub config: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            algo: None,
+            max: 3_000_000_000,
+            threads: None,
+            page_size: None,
+            detail: false,
+            executor: Executor::default(),
+            schedule: Schedule::default(),
+            load: false,
+            verify: false,
+            warmup: None,
+            runs: None,
+            format: Format::Text,
+            output: None,
+            config: None,
+        }
+    }
+}
+
+// accepts plain numbers, numbers with '_' separators and the short form 3e9
+pub fn parse_number(s: &str) -> Result<u64, String> {
+    let s = s.replace('_', "");
+    let err = || format!("invalid number: {}", s);
+    if let Some((mantissa, exponent)) = s.split_once(['e', 'E']) {
+        let mantissa: u64 = mantissa.parse().map_err(|_| err())?;
+        let exponent: u32 = exponent.parse().map_err(|_| err())?;
+        return 10u64
+            .checked_pow(exponent)
+            .and_then(|p| p.checked_mul(mantissa))
+            .ok_or_else(err);
+    }
+    s.parse().map_err(|_| err())
+}
+
+// like parse_number(), with plain numbers of any size
+fn parse_big_number(s: &str) -> Result<BigUint, String> {
+    parse_number(s).map(BigUint::from).or_else(|e| {
+        let digits = s.replace('_', "");
+        digits.parse().map_err(|_| e)
+    })
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for {}", flag))
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut chunk = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algo" => options.algo = Some(value(&mut args, &arg)?),
+            "--max" => options.max = parse_number(&value(&mut args, &arg)?)?,
+            "--threads" => {
+                let threads = parse_number(&value(&mut args, &arg)?)? as usize;
+                if threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+                options.threads = Some(threads);
+            }
+            "--page-size" => {
+                let page_size = parse_number(&value(&mut args, &arg)?)? as usize;
+                if page_size == 0 {
+                    return Err("--page-size must be at least 1".to_string());
+                }
+                options.page_size = Some(page_size);
+            }
+            "--detail" => options.detail = true,
+            "--verify" => options.verify = true,
+            "--spawn" => options.executor = Executor::Spawn,
+            "--schedule" => {
+                options.schedule = match value(&mut args, &arg)?.as_str() {
+                    "static" => Schedule::Static,
+                    "dynamic" => Schedule::default(),
+                    schedule => return Err(format!("unknown schedule: {}", schedule)),
+                }
+            }
+            "--chunk" => {
+                let slices = parse_number(&value(&mut args, &arg)?)? as usize;
+                if slices == 0 {
+                    return Err("--chunk must be at least 1".to_string());
+                }
+                chunk = Some(slices);
+            }
+            "--load" => options.load = true,
+            "--warmup" => options.warmup = Some(parse_number(&value(&mut args, &arg)?)? as usize),
+            "--runs" => {
+                let runs = parse_number(&value(&mut args, &arg)?)? as usize;
+                if runs == 0 {
+                    return Err("--runs must be at least 1".to_string());
+                }
+                options.runs = Some(runs);
+            }
+            "--format" => {
+                options.format = match value(&mut args, &arg)?.as_str() {
+                    "text" => Format::Text,
+                    "jsonl" => Format::Jsonl,
+                    "csv" => Format::Csv,
+                    format => return Err(format!("unknown format: {}", format)),
+                }
+            }
+            "--output" => options.output = Some(value(&mut args, &arg)?),
+            "--config" => options.config = Some(value(&mut args, &arg)?),
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+    if let Some(chunk) = chunk {
+        if options.schedule == Schedule::Static {
+            return Err("--chunk only applies to the dynamic schedule".to_string());
+        }
+        options.schedule = Schedule::Dynamic { chunk };
+    }
+    Ok(options)
+}
+
+// parses the arguments without the program name
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Err("missing command".to_string()),
+    };
+    match command.as_str() {
+        "count" => Ok(Command::Count(parse_options(args)?)),
+        "bench" => Ok(Command::Bench(parse_options(args)?)),
+        "tune" => Ok(Command::Tune(parse_options(args)?)),
+        "is-prime" => {
+            let numbers = args
+                .map(|arg| parse_big_number(&arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            if numbers.is_empty() {
+                return Err("missing number for is-prime".to_string());
+            }
+            Ok(Command::IsPrime(numbers))
+        }
+        "factor" => Ok(Command::Factor(
+            args.map(|arg| parse_number(&arg))
+                .collect::<Result<_, _>>()?,
+        )),
+        "list" => Ok(Command::List),
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => Err(format!("unknown command: {}", command)),
+    }
+}
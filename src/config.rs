@@ -0,0 +1,143 @@
+//! Persisted tunables, written by `parallel_primes tune` and picked up by later runs.
+//!
+//! The file has one section per algorithm:
+//!
+//! ```text
+//! [p17]
+//! threads = 16
+//! page_size = 4096
+//! max = 3000000000
+//! ```
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The tuned parameters of one algorithm, `max` is the limit they were tuned for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tunables {
+    pub threads: usize,
+    pub page_size: usize,
+    pub max: u64,
+}
+
+impl Tunables {
+    /// Returns true if `max` is within a factor of 10 of the tuned limit, the fastest parameters
+    /// for 1e6 say little about 3e9.
+    pub fn applies_to(&self, max: u64) -> bool {
+        max / 10 <= self.max && self.max / 10 <= max
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    tunables: BTreeMap<String, Tunables>,
+}
+
+fn invalid(line: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, msg),
+    )
+}
+
+impl Config {
+    /// `$PARALLEL_PRIMES_CONFIG`, else `parallel_primes/tuned.conf` in `$XDG_CONFIG_HOME` or
+    /// `$HOME/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("PARALLEL_PRIMES_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(dir.join("parallel_primes").join("tuned.conf"))
+    }
+
+    /// Returns an empty config if the file does not exist.
+    pub fn load(path: &Path) -> io::Result<Config> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e),
+        };
+        Config::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Config> {
+        let mut config = Config::default();
+        let mut section: Option<(String, BTreeMap<String, u64>)> = None;
+        let mut finish = |section: Option<(String, BTreeMap<String, u64>)>, line: usize| {
+            if let Some((name, values)) = section {
+                let get = |key: &str| {
+                    values
+                        .get(key)
+                        .copied()
+                        .ok_or_else(|| invalid(line, &format!("[{}] is missing {}", name, key)))
+                };
+                let tunables = Tunables {
+                    threads: get("threads")? as usize,
+                    page_size: get("page_size")? as usize,
+                    max: get("max")?,
+                };
+                config.tunables.insert(name, tunables);
+            }
+            Ok::<(), io::Error>(())
+        };
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                finish(section.take(), idx + 1)?;
+                section = Some((name.trim().to_string(), BTreeMap::new()));
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(idx + 1, "expected key = value"))?;
+            let key = key.trim();
+            let value: u64 = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid(idx + 1, "expected a number"))?;
+            // the sieves divide by both
+            if value == 0 && (key == "threads" || key == "page_size") {
+                return Err(invalid(idx + 1, &format!("{} must be at least 1", key)));
+            }
+            match &mut section {
+                Some((_, values)) => values.insert(key.to_string(), value),
+                None => return Err(invalid(idx + 1, "key outside of a [section]")),
+            };
+        }
+        finish(section, text.lines().count())?;
+        Ok(config)
+    }
+
+    pub fn get(&self, algorithm: &str) -> Option<Tunables> {
+        self.tunables.get(algorithm).copied()
+    }
+
+    pub fn set(&mut self, algorithm: &str, tunables: Tunables) {
+        self.tunables.insert(algorithm.to_string(), tunables);
+    }
+
+    /// Writes the config, creating the parent directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::from("# written by `parallel_primes tune`\n");
+        for (name, tunables) in &self.tunables {
+            text += &format!(
+                "\n[{}]\nthreads = {}\npage_size = {}\nmax = {}\n",
+                name, tunables.threads, tunables.page_size, tunables.max
+            );
+        }
+        fs::write(path, text)
+    }
+}
@@ -0,0 +1,88 @@
+//! A common interface for all prime counting algorithms.
+
+use crate::pool::Executor;
+use crate::schedule::{Load, Schedule};
+use crate::variants::{
+    P12a, P17Word, P1, P10, P11, P12, P16, P17, P18, P19, P2, P20, P3, P4, P5, P6, P7, P8,
+};
+use crate::wheel::Wheel;
+
+/// A prime counting algorithm, e.g. one of the `P*` variants.
+///
+/// The tunables default to "not supported", setting them is then a no-op.
+pub trait PrimeCounter: Send + Sync {
+    /// Short name used on the command line, e.g. `p17`.
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Returns the number of primes below `max`.
+    fn count(&self, max: u64) -> u64;
+
+    /// Like [`count()`](PrimeCounter::count), with the load of the threads for the algorithms
+    /// that schedule their slices.
+    fn count_with_load(&self, max: u64) -> (u64, Option<Load>) {
+        (self.count(max), None)
+    }
+
+    fn threads(&self) -> Option<usize> {
+        None
+    }
+
+    fn set_threads(&mut self, _threads: usize) {}
+
+    /// Where the threads run, [`Executor::Pool`] unless changed.
+    fn set_executor(&mut self, _executor: Executor) {}
+
+    /// How the counting sieves hand out their slices, [`Schedule::default()`] unless changed.
+    fn set_schedule(&mut self, _schedule: Schedule) {}
+
+    /// The granularity of the slices, in numbers for the sieves with one `bool` or one bit per
+    /// number, in bytes for the buffers of P20. See [`page_size_bits()`](Self::page_size_bits).
+    fn page_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Bits of memory per unit of the page size: 8 for bytes or `bool`s, 1 for packed bits.
+    fn page_size_bits(&self) -> usize {
+        8
+    }
+
+    fn set_page_size(&mut self, _page_size: usize) {}
+}
+
+/// Returns every algorithm with its default tunables, slowest first.
+pub fn all() -> Vec<Box<dyn PrimeCounter>> {
+    vec![
+        Box::new(P1),
+        Box::new(P2),
+        Box::new(P3),
+        Box::new(P4),
+        Box::new(P5),
+        Box::new(P6::default()),
+        Box::new(P7::default()),
+        Box::new(P8::default()),
+        Box::new(P10),
+        Box::new(P11),
+        Box::new(P12::default()),
+        Box::new(P12a::default()),
+        Box::new(P16::default()),
+        Box::new(P17::default()),
+        Box::new(P17Word::<u8>::default()),
+        Box::new(P17Word::<u16>::default()),
+        Box::new(P17Word::<u32>::default()),
+        Box::new(P17Word::<u128>::default()),
+        Box::new(P18::default()),
+        Box::new(P19::default()),
+        Box::new(P19 {
+            wheel: Wheel::mod210(),
+            ..P19::default()
+        }),
+        Box::new(P20::default()),
+    ]
+}
+
+/// Returns the algorithm called `name` with its default tunables.
+pub fn by_name(name: &str) -> Option<Box<dyn PrimeCounter>> {
+    all().into_iter().find(|counter| counter.name() == name)
+}
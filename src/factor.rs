@@ -0,0 +1,123 @@
+//! Factorization of `u64`s.
+//!
+//! Trial division by the primes below 4096 from [`packed_sieve()`] takes out the small factors.
+//! What is left is either prime, which [`is_prime_u64()`] decides, or is split by Pollard's rho
+//! in Brent's variant, which finds a factor `p` after about `sqrt(p)` steps.
+
+use crate::primality::{is_prime_u64, mul_mod};
+use crate::sieve::packed_sieve;
+use std::sync::OnceLock;
+
+const TRIAL_DIVISION_LIMIT: u64 = 1 << 12;
+
+// the steps of the rho iteration between two gcds
+const BATCH: u64 = 128;
+
+fn small_primes() -> &'static [u64] {
+    static PRIMES: OnceLock<Vec<u64>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        packed_sieve(TRIAL_DIVISION_LIMIT)
+            .iter_ones()
+            .map(|p| p as u64)
+            .collect()
+    })
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// returns a factor 1 < d < n of the odd composite n
+fn pollard_brent(n: u64) -> u64 {
+    for c in 1..n {
+        let f = |x: u64| ((x as u128 * x as u128 + c as u128) % n as u128) as u64;
+        let mut x;
+        let mut y = 2;
+        let mut ys = y;
+        let mut product = 1;
+        let mut g = 1;
+        let mut r = 1;
+        while g == 1 {
+            x = y;
+            for _ in 0..r {
+                y = f(y);
+            }
+            // the product of |x - y| over a batch of steps, one gcd per batch
+            let mut k = 0;
+            while k < r && g == 1 {
+                ys = y;
+                for _ in 0..BATCH.min(r - k) {
+                    y = f(y);
+                    product = mul_mod(product, x.abs_diff(y), n);
+                }
+                g = gcd(product, n);
+                k += BATCH;
+            }
+            r *= 2;
+            if g == n {
+                // the batch overshot, repeat its steps one at a time
+                g = 1;
+                while g == 1 {
+                    ys = f(ys);
+                    g = gcd(x.abs_diff(ys), n);
+                }
+            }
+        }
+        // g == n means the cycle closed without separating the factors, try the next c
+        if g != n {
+            return g;
+        }
+    }
+    unreachable!("no factor of {} found", n)
+}
+
+/// Returns the prime factors of `n` in ascending order with their exponents, nothing for 0 and 1.
+///
+/// ```
+/// use parallel_primes::factor::factor;
+/// assert_eq!(factor(360), [(2, 3), (3, 2), (5, 1)]);
+/// assert_eq!(factor(18_446_744_073_709_551_557), [(18_446_744_073_709_551_557, 1)]);
+/// ```
+pub fn factor(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = vec![];
+    let mut rest = n;
+    if rest > 1 {
+        for &p in small_primes() {
+            if p * p > rest {
+                break;
+            }
+            while rest.is_multiple_of(p) {
+                primes.push(p);
+                rest /= p;
+            }
+        }
+    }
+
+    // rest has no factor below the limit, so it is prime if it is below limit^2
+    let mut composites = vec![];
+    if rest > 1 {
+        composites.push(rest);
+    }
+    while let Some(m) = composites.pop() {
+        if m < TRIAL_DIVISION_LIMIT * TRIAL_DIVISION_LIMIT || is_prime_u64(m) {
+            primes.push(m);
+        } else {
+            let d = pollard_brent(m);
+            composites.push(d);
+            composites.push(m / d);
+        }
+    }
+
+    primes.sort_unstable();
+    let mut factors: Vec<(u64, u32)> = vec![];
+    for p in primes {
+        match factors.last_mut() {
+            Some((q, exponent)) if *q == p => *exponent += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+    factors
+}
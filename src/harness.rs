@@ -0,0 +1,116 @@
+//! Repeated timing of a [`PrimeCounter`] with warmup runs and summary statistics.
+
+use crate::counter::PrimeCounter;
+use crate::schedule::Load;
+use std::time::{Duration, Instant};
+
+/// How often a configuration is run.
+#[derive(Clone, Copy, Debug)]
+pub struct HarnessConfig {
+    /// Untimed runs before the measurement, e.g. to fault in memory and spin up the cpu clock.
+    pub warmup: usize,
+    /// Timed runs, at least 1.
+    pub runs: usize,
+}
+
+impl Default for HarnessConfig {
+    fn default() -> HarnessConfig {
+        HarnessConfig { warmup: 0, runs: 1 }
+    }
+}
+
+/// Summary of the timed runs of one (algorithm, threads, page size) cell.
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    /// Sample standard deviation, zero for a single run.
+    pub stddev: Duration,
+    /// Half-width of the 95% confidence interval of the mean.
+    pub ci95: Duration,
+    /// Number of runs outside of the Tukey fences (1.5 times the interquartile range).
+    pub outliers: usize,
+}
+
+// two-sided 95% quantiles of Student's t distribution for 1 to 30 degrees of freedom
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+// linear interpolation between the closest ranks, samples have to be sorted
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+impl Stats {
+    /// Panics if `samples` is empty.
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        assert!(!samples.is_empty(), "need at least one sample");
+        let mut secs: Vec<f64> = samples.iter().map(|d| d.as_secs_f64()).collect();
+        secs.sort_by(|a, b| a.total_cmp(b));
+
+        let n = secs.len();
+        let mean = secs.iter().sum::<f64>() / n as f64;
+        let stddev = if n > 1 {
+            let var = secs.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / (n - 1) as f64;
+            var.sqrt()
+        } else {
+            0.0
+        };
+        let t = T_95.get(n.saturating_sub(2)).copied().unwrap_or(1.96);
+        let ci95 = if n > 1 {
+            t * stddev / (n as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        let q1 = quantile(&secs, 0.25);
+        let q3 = quantile(&secs, 0.75);
+        let iqr = q3 - q1;
+        let outliers = secs
+            .iter()
+            .filter(|&&s| s < q1 - 1.5 * iqr || s > q3 + 1.5 * iqr)
+            .count();
+
+        Stats {
+            runs: n,
+            min: Duration::from_secs_f64(secs[0]),
+            median: Duration::from_secs_f64(quantile(&secs, 0.5)),
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(stddev),
+            ci95: Duration::from_secs_f64(ci95),
+            outliers,
+        }
+    }
+}
+
+/// Runs `counter` as configured, returns the prime count and the load of the last run and the
+/// statistics of the timed runs.
+///
+/// Uses the monotonic [`Instant`], so the timings are not affected by clock adjustments.
+pub fn measure(
+    counter: &dyn PrimeCounter,
+    max: u64,
+    config: HarnessConfig,
+) -> (u64, Option<Load>, Stats) {
+    for _ in 0..config.warmup {
+        counter.count(max);
+    }
+
+    let mut sum = 0;
+    let mut load = None;
+    let mut samples = vec![];
+    for _ in 0..config.runs.max(1) {
+        let time_start = Instant::now();
+        (sum, load) = counter.count_with_load(max);
+        samples.push(time_start.elapsed());
+    }
+    (sum, load, Stats::from_samples(&samples))
+}
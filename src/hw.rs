@@ -0,0 +1,65 @@
+//! Detection of the number of cores and the cache sizes of the machine.
+
+use std::fs;
+use std::thread;
+
+/// Sizes in bytes of the data caches of one core, `None` if unknown.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheSizes {
+    pub l1d: Option<usize>,
+    pub l2: Option<usize>,
+    pub l3: Option<usize>,
+}
+
+// parses sizes like "48K", "2048K" or "32M" from sysfs
+fn parse_size(s: &str) -> Option<usize> {
+    let s = s.trim();
+    let (digits, factor) = match s.chars().last()? {
+        'K' => (&s[..s.len() - 1], 1024),
+        'M' => (&s[..s.len() - 1], 1024 * 1024),
+        'G' => (&s[..s.len() - 1], 1024 * 1024 * 1024),
+        _ => (s, 1),
+    };
+    digits.parse::<usize>().ok().map(|n| n * factor)
+}
+
+impl CacheSizes {
+    /// Reads `/sys/devices/system/cpu/cpu0/cache`, all sizes are `None` on other systems.
+    pub fn detect() -> CacheSizes {
+        let mut sizes = CacheSizes::default();
+        let entries = match fs::read_dir("/sys/devices/system/cpu/cpu0/cache") {
+            Ok(entries) => entries,
+            Err(_) => return sizes,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let read = |name: &str| fs::read_to_string(path.join(name)).ok();
+            let (level, kind, size) = match (read("level"), read("type"), read("size")) {
+                (Some(level), Some(kind), Some(size)) => (level, kind, size),
+                _ => continue,
+            };
+            if kind.trim() == "Instruction" {
+                continue;
+            }
+            let size = parse_size(&size);
+            match level.trim() {
+                "1" => sizes.l1d = size,
+                "2" => sizes.l2 = size,
+                "3" => sizes.l3 = size,
+                _ => {}
+            }
+        }
+        sizes
+    }
+
+    /// Returns the buffer size in bytes for a segmented sieve, half of the L2 cache to leave room
+    /// for the sieving primes, or 128K if unknown.
+    pub fn segment_size(&self) -> usize {
+        self.l2.or(self.l1d).map_or(128 * 1024, |size| size / 2)
+    }
+}
+
+/// Returns the number of threads the machine can run in parallel.
+pub fn cores() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
@@ -0,0 +1,28 @@
+//! Prime number sieves and trial division, single and multithreaded.
+//!
+//! The `parallel_primes` binary is a front end to benchmark them against each other.
+
+pub mod bigint;
+pub mod config;
+pub mod counter;
+pub mod factor;
+pub mod harness;
+pub mod hw;
+pub mod packed_bits;
+pub mod pool;
+pub mod primality;
+pub mod rank_select;
+pub mod reference;
+pub mod report;
+pub mod schedule;
+pub mod segmented;
+pub mod sieve;
+pub mod spf_sieve;
+pub mod trial_division;
+pub mod tune;
+pub mod variants;
+pub mod wheel;
+
+pub use counter::PrimeCounter;
+pub use packed_bits::{BitIter, PackedBits, Word};
+pub use rank_select::RankSelect;
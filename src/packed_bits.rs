@@ -1,77 +1,630 @@
+//! A vector of bits, packed into unsigned integer words, 64 to an `u64` by default.
+
+use std::fmt::Debug;
+use std::iter::FusedIterator;
+use std::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Range, Shl, Shr, Sub,
+};
+
+/// The unsigned integer types a [`PackedBits`] can store its bits in.
+///
+/// Implemented for `u8`, `u16`, `u32`, `u64`, `u128` and `usize`.
+pub trait Word:
+    Copy
+    + Eq
+    + Debug
+    + Send
+    + Sync
+    + 'static
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<usize, Output = Self>
+    + Shr<usize, Output = Self>
+    + Sub<Output = Self>
+{
+    /// Number of bits in a word.
+    const BITS: usize;
+    const ZERO: Self;
+    const ONE: Self;
+    /// All bits set.
+    const ONES: Self;
+
+    fn count_ones(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+    fn leading_zeros(self) -> u32;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                const BITS: usize = <$t>::BITS as usize;
+                const ZERO: $t = 0;
+                const ONE: $t = 1;
+                const ONES: $t = <$t>::MAX;
+
+                fn count_ones(self) -> u32 {
+                    <$t>::count_ones(self)
+                }
+
+                fn trailing_zeros(self) -> u32 {
+                    <$t>::trailing_zeros(self)
+                }
+
+                fn leading_zeros(self) -> u32 {
+                    <$t>::leading_zeros(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(u8, u16, u32, u64, u128, usize);
+
+/// A vector of bits, packed `W::BITS` to a word of type `W`.
+///
+/// The unused bits of the last word are always 0.
 #[derive(Clone)]
-pub struct PackedBits {
+pub struct PackedBits<W: Word = u64> {
     size: usize,
-    data: Vec<u64>,
-    or_table: Vec<u64>,
-    and_table: Vec<u64>,
-}
-
-impl PackedBits {
-    pub fn new_set(n: usize, initial_state: bool) -> PackedBits {
-        let mut or_table = vec![];
-        let mut and_table = vec![];
-        for i in 0..64 {
-            let or = 1 << i;
-            let and = 0xffffffffffffffff ^ or;
-            or_table.push(or);
-            and_table.push(and);
-        }
-        let size = (n + 63) / 64;
-        PackedBits {
-            size: n,
-            data: vec![if initial_state { 0xffffffffffffffff } else { 0 }; size],
+    data: Vec<W>,
+    or_table: Vec<W>,
+    and_table: Vec<W>,
+}
+
+// the single bit masks for setting and clearing bits
+fn tables<W: Word>() -> (Vec<W>, Vec<W>) {
+    let mut or_table = vec![];
+    let mut and_table = vec![];
+    for i in 0..W::BITS {
+        let or = W::ONE << i;
+        let and = W::ONES ^ or;
+        or_table.push(or);
+        and_table.push(and);
+    }
+    (or_table, and_table)
+}
+
+// the bits lower..upper of a word, lower is below and upper at most W::BITS
+fn mask<W: Word>(lower: usize, upper: usize) -> W {
+    if upper == 0 {
+        return W::ZERO;
+    }
+    (W::ONES >> (W::BITS - upper)) & (W::ONES << lower)
+}
+
+impl<W: Word> PackedBits<W> {
+    /// Creates `n` bits, all set to `initial_state`.
+    pub fn new_set(n: usize, initial_state: bool) -> PackedBits<W> {
+        let size = n.div_ceil(W::BITS);
+        PackedBits::from_words_len(vec![if initial_state { W::ONES } else { W::ZERO }; size], n)
+    }
+
+    /// Creates `W::BITS * words.len()` bits, bit `i` of word `j` is the bit at index
+    /// `W::BITS * j + i`.
+    pub fn from_words(words: Vec<W>) -> PackedBits<W> {
+        let len = words.len() * W::BITS;
+        PackedBits::from_words_len(words, len)
+    }
+
+    /// Like [`from_words()`](PackedBits::from_words), but only the first `len` bits are used, the
+    /// rest of the last word is cleared.
+    ///
+    /// Panics unless there are exactly `len.div_ceil(W::BITS)` words.
+    pub fn from_words_len(words: Vec<W>, len: usize) -> PackedBits<W> {
+        assert_eq!(
+            words.len(),
+            len.div_ceil(W::BITS),
+            "{} words for {} bits",
+            words.len(),
+            len
+        );
+        let (or_table, and_table) = tables();
+        let mut bits = PackedBits {
+            size: len,
+            data: words,
             or_table,
             and_table,
+        };
+        bits.clear_tail();
+        bits
+    }
+
+    // clears the unused bits of the last word
+    fn clear_tail(&mut self) {
+        let offset = self.size % W::BITS;
+        if offset > 0 {
+            let last = self.data.len() - 1;
+            self.data[last] = self.data[last] & mask(0, offset);
+        }
+    }
+
+    /// Moves the bits of `other` to the end of `self`, leaving `other` empty.
+    ///
+    /// If the length of `self` is not a multiple of `W::BITS`, the words of `other` are shifted
+    /// into place.
+    pub fn append(&mut self, other: &mut PackedBits<W>) {
+        let offset = self.size % W::BITS;
+        if offset == 0 {
+            self.data.append(&mut other.data);
+        } else {
+            self.data.reserve(other.data.len());
+            for word in other.data.drain(..) {
+                let last = self.data.len() - 1;
+                self.data[last] = self.data[last] | word << offset;
+                self.data.push(word >> (W::BITS - offset));
+            }
+        }
+        self.size += other.size;
+        other.size = 0;
+        self.data.truncate(self.size.div_ceil(W::BITS));
+    }
+
+    /// Shortens to `len` bits, does nothing if there are already `len` bits or less.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.size {
+            return;
         }
+        self.size = len;
+        self.data.truncate(len.div_ceil(W::BITS));
+        self.clear_tail();
     }
 
-    pub fn append(&mut self, other: &mut PackedBits) {
-        if self.len() % 64 != 0 {
-            panic!(
-                "len={} and len%64={} but must be 0",
-                self.len(),
-                self.len() % 64
-            );
+    /// Splits off the bits `at..len()` and returns them, `self` keeps the bits `0..at`.
+    ///
+    /// Panics if `at > len()`.
+    pub fn split_off(&mut self, at: usize) -> PackedBits<W> {
+        assert!(at <= self.size, "at={} but len={}", at, self.size);
+        let mut other = PackedBits::<W>::new_set(self.size - at, false);
+        let first = at / W::BITS;
+        let offset = at % W::BITS;
+        for (i, word) in other.data.iter_mut().enumerate() {
+            let lower = self.data[first + i] >> offset;
+            let upper = match self.data.get(first + i + 1) {
+                Some(&next) if offset > 0 => next << (W::BITS - offset),
+                _ => W::ZERO,
+            };
+            *word = lower | upper;
         }
-        self.size += other.len();
-        self.data.append(&mut other.data);
+        other.clear_tail();
+        self.truncate(at);
+        other
     }
 
+    /// Returns the number of bits.
     pub fn len(&self) -> usize {
         self.size
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Sets the bit at `idx` to 0.
     pub fn clear(&mut self, idx: usize) {
-        let addr = idx / 64;
-        let offset = idx % 64;
+        let addr = idx / W::BITS;
+        let offset = idx % W::BITS;
         let z = self.and_table[offset];
-        self.data[addr] &= z;
+        self.data[addr] = self.data[addr] & z;
+    }
+
+    /// Sets the bit at `idx` to 1.
+    pub fn set(&mut self, idx: usize) {
+        assert!(idx < self.size, "idx={} but len={}", idx, self.size);
+        let addr = idx / W::BITS;
+        let offset = idx % W::BITS;
+        let z = self.or_table[offset];
+        self.data[addr] = self.data[addr] | z;
+    }
+
+    /// Flips the bit at `idx`.
+    pub fn toggle(&mut self, idx: usize) {
+        assert!(idx < self.size, "idx={} but len={}", idx, self.size);
+        let addr = idx / W::BITS;
+        let offset = idx % W::BITS;
+        let z = self.or_table[offset];
+        self.data[addr] = self.data[addr] ^ z;
+    }
+
+    /// Sets the bit at `idx` to `value`.
+    pub fn assign(&mut self, idx: usize, value: bool) {
+        if value {
+            self.set(idx);
+        } else {
+            self.clear(idx);
+        }
+    }
+
+    // sets or clears the bits in range, a word at a time
+    fn fill_range(&mut self, range: Range<usize>, value: bool) {
+        assert!(
+            range.end <= self.size,
+            "range={:?} but len={}",
+            range,
+            self.size
+        );
+        if range.start >= range.end {
+            return;
+        }
+        let first = range.start / W::BITS;
+        let last = (range.end - 1) / W::BITS;
+        for addr in first..=last {
+            let lower = if addr == first {
+                range.start % W::BITS
+            } else {
+                0
+            };
+            let upper = if addr == last {
+                (range.end - 1) % W::BITS + 1
+            } else {
+                W::BITS
+            };
+            let m: W = mask(lower, upper);
+            if value {
+                self.data[addr] = self.data[addr] | m;
+            } else {
+                self.data[addr] = self.data[addr] & !m;
+            }
+        }
+    }
+
+    /// Sets all bits in `range` to 1.
+    pub fn set_range(&mut self, range: Range<usize>) {
+        self.fill_range(range, true);
+    }
+
+    /// Sets all bits in `range` to 0.
+    pub fn clear_range(&mut self, range: Range<usize>) {
+        self.fill_range(range, false);
+    }
+
+    /// Returns the word holding the bits `W::BITS * idx..W::BITS * (idx + 1)`.
+    pub fn get_word(&self, idx: usize) -> W {
+        self.data[idx]
+    }
+
+    /// Returns the underlying words, the unused bits of the last word are 0.
+    pub fn as_words(&self) -> &[W] {
+        &self.data
+    }
+
+    // combines the words of other into self, both must have the same length
+    fn zip_words(&mut self, other: &PackedBits<W>, f: impl Fn(W, W) -> W) {
+        assert_eq!(
+            self.size, other.size,
+            "len={} but other len={}",
+            self.size, other.size
+        );
+        for (word, &o) in self.data.iter_mut().zip(&other.data) {
+            *word = f(*word, o);
+        }
+    }
+
+    /// Sets each bit to `self[i] & other[i]`.
+    ///
+    /// Panics unless both have the same length, like the other combining operations.
+    pub fn and_assign(&mut self, other: &PackedBits<W>) {
+        self.zip_words(other, |a, b| a & b);
+    }
+
+    /// Sets each bit to `self[i] | other[i]`.
+    pub fn or_assign(&mut self, other: &PackedBits<W>) {
+        self.zip_words(other, |a, b| a | b);
+    }
+
+    /// Sets each bit to `self[i] ^ other[i]`.
+    pub fn xor_assign(&mut self, other: &PackedBits<W>) {
+        self.zip_words(other, |a, b| a ^ b);
+    }
+
+    /// Flips every bit.
+    pub fn not_assign(&mut self) {
+        for word in &mut self.data {
+            *word = !*word;
+        }
+        self.clear_tail();
+    }
+
+    /// Returns `self[i] & other[i]`, same as `&self & &other`.
+    pub fn and(&self, other: &PackedBits<W>) -> PackedBits<W> {
+        let mut result = self.clone();
+        result.and_assign(other);
+        result
+    }
+
+    /// Returns `self[i] | other[i]`, same as `&self | &other`.
+    pub fn or(&self, other: &PackedBits<W>) -> PackedBits<W> {
+        let mut result = self.clone();
+        result.or_assign(other);
+        result
+    }
+
+    /// Returns `self[i] ^ other[i]`, same as `&self ^ &other`.
+    pub fn xor(&self, other: &PackedBits<W>) -> PackedBits<W> {
+        let mut result = self.clone();
+        result.xor_assign(other);
+        result
+    }
+
+    /// Returns every bit flipped, same as `!&self`.
+    pub fn not(&self) -> PackedBits<W> {
+        let mut result = self.clone();
+        result.not_assign();
+        result
     }
 
+    // the W::BITS bits from start on, bits outside 0..len() are 0
+    fn word_at(&self, start: isize) -> W {
+        let bits = W::BITS as isize;
+        let addr = start.div_euclid(bits);
+        let offset = start.rem_euclid(bits) as usize;
+        let get = |addr: isize| match usize::try_from(addr) {
+            Ok(addr) => self.data.get(addr).copied().unwrap_or(W::ZERO),
+            Err(_) => W::ZERO,
+        };
+        if offset == 0 {
+            get(addr)
+        } else {
+            get(addr) >> offset | get(addr + 1) << (W::BITS - offset)
+        }
+    }
+
+    // combines other[i + shift] into self[i], other can have any length
+    fn zip_shifted(&mut self, other: &PackedBits<W>, shift: isize, f: impl Fn(W, W) -> W) {
+        for addr in 0..self.data.len() {
+            let o = other.word_at((addr * W::BITS) as isize + shift);
+            self.data[addr] = f(self.data[addr], o);
+        }
+        self.clear_tail();
+    }
+
+    /// Sets each bit to `self[i] & other[i + shift]`, the bits outside of `other` count as 0.
+    pub fn and_shifted(&mut self, other: &PackedBits<W>, shift: isize) {
+        self.zip_shifted(other, shift, |a, b| a & b);
+    }
+
+    /// Sets each bit to `self[i] | other[i + shift]`, the bits outside of `other` count as 0.
+    pub fn or_shifted(&mut self, other: &PackedBits<W>, shift: isize) {
+        self.zip_shifted(other, shift, |a, b| a | b);
+    }
+
+    /// Sets each bit to `self[i] ^ other[i + shift]`, the bits outside of `other` count as 0.
+    pub fn xor_shifted(&mut self, other: &PackedBits<W>, shift: isize) {
+        self.zip_shifted(other, shift, |a, b| a ^ b);
+    }
+
+    /// Returns the bits `self[i + shift]` with the same length, the bits shifted in are 0.
+    ///
+    /// E.g. `&primes & &primes.shifted(2)` of a sieve are the lower primes of the twin primes.
+    pub fn shifted(&self, shift: isize) -> PackedBits<W> {
+        let mut result = PackedBits::new_set(self.size, false);
+        result.or_shifted(self, shift);
+        result
+    }
+
+    // number of 1 bits in range, a word at a time
+    fn count_ones_in(&self, range: Range<usize>) -> usize {
+        if range.start >= range.end {
+            return 0;
+        }
+        let first = range.start / W::BITS;
+        let last = (range.end - 1) / W::BITS;
+        let mut sum = 0;
+        for addr in first..=last {
+            let lower = if addr == first {
+                range.start % W::BITS
+            } else {
+                0
+            };
+            let upper = if addr == last {
+                (range.end - 1) % W::BITS + 1
+            } else {
+                W::BITS
+            };
+            sum += (self.data[addr] & mask(lower, upper)).count_ones() as usize;
+        }
+        sum
+    }
+
+    fn bit_iter(&self, range: Range<usize>, zeros: bool) -> BitIter<'_, W> {
+        assert!(
+            range.end <= self.size,
+            "range={:?} but len={}",
+            range,
+            self.size
+        );
+        let ones = self.count_ones_in(range.clone());
+        let remaining = if zeros { range.len() - ones } else { ones };
+        BitIter {
+            words: &self.data,
+            zeros,
+            front: range.start,
+            back: range.end,
+            remaining,
+        }
+    }
+
+    /// Returns the indices of the 1 bits in ascending order, e.g. the primes of a sieve.
+    pub fn iter_ones(&self) -> BitIter<'_, W> {
+        self.bit_iter(0..self.size, false)
+    }
+
+    /// Returns the indices of the 0 bits in ascending order.
+    pub fn iter_zeros(&self) -> BitIter<'_, W> {
+        self.bit_iter(0..self.size, true)
+    }
+
+    /// Like [`iter_ones()`](PackedBits::iter_ones), but only the bits in `range`.
+    pub fn iter_ones_in(&self, range: Range<usize>) -> BitIter<'_, W> {
+        self.bit_iter(range, false)
+    }
+
+    /// Like [`iter_zeros()`](PackedBits::iter_zeros), but only the bits in `range`.
+    pub fn iter_zeros_in(&self, range: Range<usize>) -> BitIter<'_, W> {
+        self.bit_iter(range, true)
+    }
+
+    /// Returns `true` if the bit at `idx` is 1.
     pub fn is_set(&self, idx: usize) -> bool {
-        let addr = idx / 64;
-        let offset = idx % 64;
+        let addr = idx / W::BITS;
+        let offset = idx % W::BITS;
         let z = self.or_table[offset];
 
-        self.data[addr] & z > 0
+        self.data[addr] & z != W::ZERO
     }
 
+    /// Returns the number of 1 bits in `0..n`.
+    ///
+    /// Scans the words from the start, for repeated queries see [`RankSelect`](crate::RankSelect).
     pub fn count_ones(&self, n: usize) -> usize {
-        let mut sum: u32 = 0;
-        let upper = n / 64;
+        let mut sum: usize = 0;
+        let upper = n / W::BITS;
         for d in &self.data[0..upper] {
-            sum += d.count_ones();
+            sum += d.count_ones() as usize;
         }
 
-        let lower = n % 64;
+        let lower = n % W::BITS;
         if lower > 0 {
             let r = self.data[upper];
             for i in 0..lower {
-                if r & (1 << i) != 0 {
+                if r & (W::ONE << i) != W::ZERO {
                     sum += 1;
                 }
             }
         }
-        sum as usize
+        sum
+    }
+}
+
+impl<W: Word> BitAnd for &PackedBits<W> {
+    type Output = PackedBits<W>;
+
+    fn bitand(self, other: &PackedBits<W>) -> PackedBits<W> {
+        self.and(other)
+    }
+}
+
+impl<W: Word> BitOr for &PackedBits<W> {
+    type Output = PackedBits<W>;
+
+    fn bitor(self, other: &PackedBits<W>) -> PackedBits<W> {
+        self.or(other)
+    }
+}
+
+impl<W: Word> BitXor for &PackedBits<W> {
+    type Output = PackedBits<W>;
+
+    fn bitxor(self, other: &PackedBits<W>) -> PackedBits<W> {
+        self.xor(other)
+    }
+}
+
+impl<W: Word> Not for &PackedBits<W> {
+    type Output = PackedBits<W>;
+
+    fn not(self) -> PackedBits<W> {
+        PackedBits::not(self)
+    }
+}
+
+impl<W: Word> Not for PackedBits<W> {
+    type Output = PackedBits<W>;
+
+    fn not(mut self) -> PackedBits<W> {
+        self.not_assign();
+        self
+    }
+}
+
+impl<W: Word> BitAndAssign<&PackedBits<W>> for PackedBits<W> {
+    fn bitand_assign(&mut self, other: &PackedBits<W>) {
+        self.and_assign(other);
+    }
+}
+
+impl<W: Word> BitOrAssign<&PackedBits<W>> for PackedBits<W> {
+    fn bitor_assign(&mut self, other: &PackedBits<W>) {
+        self.or_assign(other);
+    }
+}
+
+impl<W: Word> BitXorAssign<&PackedBits<W>> for PackedBits<W> {
+    fn bitxor_assign(&mut self, other: &PackedBits<W>) {
+        self.xor_assign(other);
+    }
+}
+
+/// Iterator over the indices of the 1 (or 0) bits of a [`PackedBits`].
+///
+/// Skips whole words without matching bits, and finds the bits in a word with
+/// `trailing_zeros`/`leading_zeros`.
+#[derive(Clone)]
+pub struct BitIter<'a, W: Word = u64> {
+    words: &'a [W],
+    zeros: bool,
+    // the bits front..back are not visited yet
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+impl<W: Word> BitIter<'_, W> {
+    fn word(&self, addr: usize) -> W {
+        if self.zeros {
+            !self.words[addr]
+        } else {
+            self.words[addr]
+        }
+    }
+}
+
+// there are exactly `remaining` matching bits in front..back, so the loops always find one before
+// leaving the range, including the inverted unused bits of the last word when looking for zeros
+impl<W: Word> Iterator for BitIter<'_, W> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let mut addr = self.front / W::BITS;
+        let mut word = self.word(addr) & mask(self.front % W::BITS, W::BITS);
+        while word == W::ZERO {
+            addr += 1;
+            word = self.word(addr);
+        }
+        let idx = addr * W::BITS + word.trailing_zeros() as usize;
+        self.front = idx + 1;
+        self.remaining -= 1;
+        Some(idx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<W: Word> DoubleEndedIterator for BitIter<'_, W> {
+    fn next_back(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let mut addr = (self.back - 1) / W::BITS;
+        let mut word = self.word(addr) & mask(0, (self.back - 1) % W::BITS + 1);
+        while word == W::ZERO {
+            addr -= 1;
+            word = self.word(addr);
+        }
+        let idx = addr * W::BITS + W::BITS - 1 - word.leading_zeros() as usize;
+        self.back = idx;
+        self.remaining -= 1;
+        Some(idx)
     }
 }
+
+impl<W: Word> ExactSizeIterator for BitIter<'_, W> {}
+
+impl<W: Word> FusedIterator for BitIter<'_, W> {}
@@ -0,0 +1,162 @@
+//! A persistent pool of worker threads with a shared work queue, std only.
+//!
+//! The threaded sieves take an [`Executor`] like they take a thread count. [`Executor::Pool`]
+//! submits their slices to the process wide [`ThreadPool::shared()`], so the OS threads are created
+//! once instead of per batch, [`Executor::Spawn`] starts a fresh thread per slice, to benchmark the
+//! two against each other.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A fixed number of worker threads taking jobs from one queue.
+///
+/// Dropping the pool lets the workers finish the queued jobs and joins them.
+pub struct ThreadPool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// The result of a job submitted to a [`ThreadPool`] or to [`Workers`].
+pub struct Task<T> {
+    inner: TaskInner<T>,
+}
+
+enum TaskInner<T> {
+    Pool(Receiver<Result<T, Box<dyn Any + Send>>>),
+    Thread(JoinHandle<T>),
+}
+
+impl<T> Task<T> {
+    /// Waits for the job and returns its result, a panic of the job is resumed here.
+    pub fn join(self) -> T {
+        let result = match self.inner {
+            TaskInner::Pool(receiver) => receiver.recv().expect("worker thread died"),
+            TaskInner::Thread(handle) => handle.join(),
+        };
+        match result {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl ThreadPool {
+    /// Starts `threads` worker threads, at least one.
+    pub fn new(threads: usize) -> ThreadPool {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || loop {
+                    // the lock is released before running the job
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+        ThreadPool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Returns the pool shared by the whole process, with at least `threads` workers.
+    ///
+    /// The pool is started on first use and replaced by one with `threads` workers when a caller
+    /// needs more, so there is only one pool of the largest size asked for. A replaced pool shuts
+    /// down when its last user drops it.
+    pub fn shared(threads: usize) -> Arc<ThreadPool> {
+        static POOL: Mutex<Option<Arc<ThreadPool>>> = Mutex::new(None);
+        let mut pool = POOL.lock().unwrap();
+        match pool.as_ref() {
+            Some(shared) if shared.threads() >= threads => shared.clone(),
+            _ => {
+                let larger = Arc::new(ThreadPool::new(threads));
+                *pool = Some(larger.clone());
+                larger
+            }
+        }
+    }
+
+    /// Returns the number of worker threads.
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queues `f` to run on the next free worker.
+    pub fn submit<T, F>(&self, f: F) -> Task<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let job = Box::new(move || {
+            // a panicking job must not take the worker down with it
+            let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        });
+        self.sender.as_ref().unwrap().send(job).unwrap();
+        Task {
+            inner: TaskInner::Pool(receiver),
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Where the threaded sieves run their slices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Executor {
+    /// The workers of [`ThreadPool::shared()`].
+    #[default]
+    Pool,
+    /// A new OS thread per job.
+    Spawn,
+}
+
+impl Executor {
+    /// Returns the workers to run up to `threads` jobs at a time on.
+    pub fn workers(self, threads: usize) -> Workers {
+        match self {
+            Executor::Pool => Workers::Pool(ThreadPool::shared(threads)),
+            Executor::Spawn => Workers::Spawn,
+        }
+    }
+}
+
+/// The threads of an [`Executor`], taking the jobs of one sieve.
+#[derive(Clone)]
+pub enum Workers {
+    Spawn,
+    Pool(Arc<ThreadPool>),
+}
+
+impl Workers {
+    pub fn submit<T, F>(&self, f: F) -> Task<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        match self {
+            Workers::Spawn => Task {
+                inner: TaskInner::Thread(thread::spawn(f)),
+            },
+            Workers::Pool(pool) => pool.submit(f),
+        }
+    }
+}
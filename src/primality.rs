@@ -0,0 +1,260 @@
+//! Primality tests for single numbers, without sieving everything below them.
+//!
+//! [`is_prime_u64()`] is Miller–Rabin with the first twelve primes as witnesses, which is
+//! deterministic for every `n` below 3.18 * 10^23 and so for every `u64`. The products are done in
+//! `u128`, which compiles to a single widening multiplication on 64-bit targets.
+//!
+//! Larger numbers, e.g. with hundreds of digits, are [`BigUint`]s checked by [`is_prime_bpsw()`].
+
+use crate::bigint::BigUint;
+use crate::sieve::packed_sieve;
+use std::sync::OnceLock;
+
+// the deterministic witnesses for n < 318665857834031151167461 (about 3.18 * 10^23), also used
+// for trial division
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Returns `a * b % m`, `m` must not be 0.
+pub fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// Returns `base^exp % m`, `m` must not be 0.
+pub fn pow_mod(base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    let mut base = base % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// true if n passes the strong probable prime test to base a, n odd and n - 1 = d * 2^s
+fn strong_probable_prime(n: u64, d: u64, s: u32, a: u64) -> bool {
+    let mut x = pow_mod(a, d, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Deterministic Miller–Rabin test, correct for every `u64`.
+///
+/// ```
+/// use parallel_primes::primality::is_prime_u64;
+/// assert!(is_prime_u64(18_446_744_073_709_551_557)); // the largest prime below 2^64
+/// assert!(!is_prime_u64(3_215_031_751)); // a strong pseudoprime to the bases 2, 3, 5 and 7
+/// ```
+pub fn is_prime_u64(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    // no factor up to 37, so n is prime if it is below 41 * 41
+    if n < 41 * 41 {
+        return true;
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    WITNESSES.iter().all(|&a| strong_probable_prime(n, d, s, a))
+}
+
+/// The limit of the trial division before the Baillie–PSW test.
+const TRIAL_DIVISION_LIMIT: u64 = 1 << 13;
+
+// the odd primes below TRIAL_DIVISION_LIMIT
+fn small_primes() -> &'static [u64] {
+    static PRIMES: OnceLock<Vec<u64>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        packed_sieve(TRIAL_DIVISION_LIMIT)
+            .iter_ones()
+            .skip(1)
+            .map(|p| p as u64)
+            .collect()
+    })
+}
+
+/// Baillie–PSW test: trial division by the primes below 8192, a strong probable prime test to
+/// base 2 and a strong Lucas probable prime test.
+///
+/// No composite passing it is known, numbers that fit in a `u64` are decided by
+/// [`is_prime_u64()`] instead, which is proven correct.
+///
+/// ```
+/// use parallel_primes::bigint::BigUint;
+/// use parallel_primes::primality::is_prime_bpsw;
+/// let m127 = &(&BigUint::one() << 127) - &BigUint::one();
+/// assert!(is_prime_bpsw(&m127));
+/// assert!(!is_prime_bpsw(&(&m127 * &m127)));
+/// ```
+pub fn is_prime_bpsw(n: &BigUint) -> bool {
+    if let Some(n) = n.to_u64() {
+        return is_prime_u64(n);
+    }
+    // n is larger than every small prime, so any divisor makes it composite
+    if n.is_even() || small_primes().iter().any(|&p| n.rem_u64(p) == 0) {
+        return false;
+    }
+    let one = BigUint::one();
+    let n_minus_1 = n - &one;
+    let s = n_minus_1.trailing_zeros();
+    let d = &n_minus_1 >> s as usize;
+    let mut x = BigUint::from(2u64).pow_mod(&d, n);
+    if x != one && x != n_minus_1 {
+        let mut passed = false;
+        for _ in 1..s {
+            x = &(&x * &x) % n;
+            if x == n_minus_1 {
+                passed = true;
+                break;
+            }
+        }
+        if !passed {
+            return false;
+        }
+    }
+    is_strong_lucas_prp(n)
+}
+
+// the Jacobi symbol (a/n) for odd n > 0
+fn jacobi_u64(mut a: u64, mut n: u64) -> i32 {
+    let mut result = 1;
+    a %= n;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+        (a, n) = (n, a);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+    if n == 1 {
+        result
+    } else {
+        0
+    }
+}
+
+// the Jacobi symbol (a/n) for odd a and odd n > |a|, by quadratic reciprocity
+fn jacobi(a: i64, n: &BigUint) -> i32 {
+    let m = a.unsigned_abs();
+    let n_mod_4 = n.rem_u64(4);
+    let mut result = jacobi_u64(n.rem_u64(m), m);
+    if m % 4 == 3 && n_mod_4 == 3 {
+        result = -result;
+    }
+    // (-1/n) is -1 for n = 3 mod 4
+    if a < 0 && n_mod_4 == 3 {
+        result = -result;
+    }
+    result
+}
+
+// a mod n for a signed a
+fn signed_mod(a: i64, n: &BigUint) -> BigUint {
+    let r = &BigUint::from(a.unsigned_abs()) % n;
+    if a >= 0 || r.is_zero() {
+        r
+    } else {
+        n - &r
+    }
+}
+
+fn sub_mod(a: &BigUint, b: &BigUint, n: &BigUint) -> BigUint {
+    if a >= b {
+        a - b
+    } else {
+        &(a + n) - b
+    }
+}
+
+// a / 2 mod n for odd n
+fn half_mod(a: &BigUint, n: &BigUint) -> BigUint {
+    if a.is_even() {
+        a >> 1
+    } else {
+        &(a + n) >> 1
+    }
+}
+
+/// Strong Lucas probable prime test with the parameters of Selfridge's method A: `D` is the
+/// first of 5, -7, 9, -11, ... with the Jacobi symbol `(D/n) = -1`, `P = 1` and `Q = (1 - D) / 4`.
+///
+/// Only meaningful for odd `n` larger than 2^32 without small factors, as used by
+/// [`is_prime_bpsw()`]. The composites passing it are the strong Lucas pseudoprimes, 5459, 5777,
+/// 10877 and so on.
+pub fn is_strong_lucas_prp(n: &BigUint) -> bool {
+    let root = n.sqrt();
+    if &(&root * &root) == n {
+        // a square never has (D/n) = -1
+        return false;
+    }
+    let mut d: i64 = 5;
+    loop {
+        match jacobi(d, n) {
+            -1 => break,
+            // n has the factor |D|, unless it is |D| itself
+            0 => return n.to_u64() == Some(d.unsigned_abs()),
+            _ => d = if d > 0 { -d - 2 } else { -d + 2 },
+        }
+    }
+    let q = (1 - d) / 4;
+    let d_mod = signed_mod(d, n);
+    let q_mod = signed_mod(q, n);
+
+    // n + 1 = k * 2^s with odd k
+    let n_plus_1 = n + &BigUint::one();
+    let s = n_plus_1.trailing_zeros();
+    let k = &n_plus_1 >> s as usize;
+
+    let mul = |a: &BigUint, b: &BigUint| &(a * b) % n;
+    let add = |a: &BigUint, b: &BigUint| &(a + b) % n;
+
+    // U_1 = 1, V_1 = P = 1, then doubling and incrementing the index along the bits of k:
+    // U_2j = U_j V_j, V_2j = V_j^2 - 2 Q^j, U_j+1 = (U_j + V_j) / 2, V_j+1 = (D U_j + V_j) / 2
+    let mut u = BigUint::one();
+    let mut v = BigUint::one();
+    let mut q_k = q_mod.clone();
+    for i in (0..k.bits() - 1).rev() {
+        u = mul(&u, &v);
+        v = sub_mod(&mul(&v, &v), &add(&q_k, &q_k), n);
+        q_k = mul(&q_k, &q_k);
+        if k.bit(i) {
+            (u, v) = (
+                half_mod(&add(&u, &v), n),
+                half_mod(&add(&mul(&d_mod, &u), &v), n),
+            );
+            q_k = mul(&q_k, &q_mod);
+        }
+    }
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub_mod(&mul(&v, &v), &add(&q_k, &q_k), n);
+        if v.is_zero() {
+            return true;
+        }
+        q_k = mul(&q_k, &q_k);
+    }
+    false
+}
@@ -0,0 +1,125 @@
+//! Constant time rank and select queries on [`PackedBits`].
+//!
+//! Built on top of a sieve, `rank(n + 1)` is the number of primes up to `n` and `select(k)` the
+//! `k + 1`-th prime:
+//!
+//! ```
+//! use parallel_primes::rank_select::RankSelect;
+//! use parallel_primes::sieve::packed_sieve;
+//!
+//! let primes = RankSelect::new(packed_sieve(1000));
+//! assert_eq!(primes.rank(1001), 168);
+//! assert_eq!(primes.select(99), Some(541));
+//! ```
+
+use crate::packed_bits::{PackedBits, Word};
+
+// bits per block, the number of 1 bits before each block is stored
+const BLOCK_BITS: usize = 512;
+// every SAMPLE_RATE-th 1 bit, the block it is in is stored
+const SAMPLE_RATE: usize = 512;
+
+/// [`PackedBits`] with an index of about 3% of its size for rank and select queries.
+pub struct RankSelect<W: Word = u64> {
+    bits: PackedBits<W>,
+    // block_ranks[b] is the number of 1 bits before block b, the last entry is the total
+    block_ranks: Vec<u64>,
+    // samples[i] is the block holding the 1 bit number i * SAMPLE_RATE
+    samples: Vec<u32>,
+}
+
+// index of the 1 bit number k (0-based) in word, which has more than k 1 bits
+fn select_in_word<W: Word>(mut word: W, k: usize) -> usize {
+    for _ in 0..k {
+        word = word & (word - W::ONE);
+    }
+    word.trailing_zeros() as usize
+}
+
+impl<W: Word> RankSelect<W> {
+    // words per block, W::BITS is at most 128
+    const BLOCK_WORDS: usize = BLOCK_BITS / W::BITS;
+
+    /// Builds the index with one pass over the words.
+    pub fn new(bits: PackedBits<W>) -> RankSelect<W> {
+        let words = bits.as_words();
+        let mut block_ranks = Vec::with_capacity(words.len() / Self::BLOCK_WORDS + 2);
+        let mut samples = vec![];
+        let mut rank = 0;
+        for (block, chunk) in words.chunks(Self::BLOCK_WORDS).enumerate() {
+            block_ranks.push(rank);
+            let ones: u64 = chunk.iter().map(|w| w.count_ones() as u64).sum();
+            // the samples falling into this block
+            while (samples.len() * SAMPLE_RATE) < (rank + ones) as usize {
+                samples.push(block as u32);
+            }
+            rank += ones;
+        }
+        block_ranks.push(rank);
+        RankSelect {
+            bits,
+            block_ranks,
+            samples,
+        }
+    }
+
+    pub fn bits(&self) -> &PackedBits<W> {
+        &self.bits
+    }
+
+    pub fn into_inner(self) -> PackedBits<W> {
+        self.bits
+    }
+
+    /// Returns the total number of 1 bits.
+    pub fn count_ones(&self) -> usize {
+        *self.block_ranks.last().unwrap() as usize
+    }
+
+    /// Returns the number of 1 bits in `0..n`, like [`PackedBits::count_ones()`].
+    ///
+    /// Panics if `n > len()`.
+    pub fn rank(&self, n: usize) -> usize {
+        assert!(n <= self.bits.len(), "n={} but len={}", n, self.bits.len());
+        let words = self.bits.as_words();
+        let block = n / BLOCK_BITS;
+        let mut rank = self.block_ranks[block] as usize;
+        let word = n / W::BITS;
+        for w in &words[block * Self::BLOCK_WORDS..word] {
+            rank += w.count_ones() as usize;
+        }
+        let offset = n % W::BITS;
+        if offset > 0 {
+            rank += (words[word] & ((W::ONE << offset) - W::ONE)).count_ones() as usize;
+        }
+        rank
+    }
+
+    /// Returns the index of the 1 bit number `k`, counting from 0, or `None` if there are at most
+    /// `k` 1 bits.
+    pub fn select(&self, k: usize) -> Option<usize> {
+        if k >= self.count_ones() {
+            return None;
+        }
+        // the block is between the samples before and after k, binary search over their ranks
+        let sample = k / SAMPLE_RATE;
+        let lower = self.samples[sample] as usize;
+        let upper = self
+            .samples
+            .get(sample + 1)
+            .map_or(self.block_ranks.len() - 1, |&b| b as usize + 1);
+        let block =
+            lower + self.block_ranks[lower..upper].partition_point(|&rank| rank as usize <= k) - 1;
+
+        let words = self.bits.as_words();
+        let mut remaining = k - self.block_ranks[block] as usize;
+        for (addr, &word) in words.iter().enumerate().skip(block * Self::BLOCK_WORDS) {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                return Some(addr * W::BITS + select_in_word(word, remaining));
+            }
+            remaining -= ones;
+        }
+        unreachable!("k={} is below the number of 1 bits", k)
+    }
+}
@@ -0,0 +1,89 @@
+//! Known prime counts, and an independent combinatorial π(x) to verify arbitrary limits.
+
+// (max, number of primes below max), only for max that are not prime themselves
+const KNOWN: [(u64, u64); 30] = [
+    (1, 0),
+    (10, 4),
+    (100, 25),
+    (300, 62),
+    (1_000, 168),
+    (3_000, 430),
+    (10_000, 1_229),
+    (30_000, 3_245),
+    (100_000, 9_592),
+    (300_000, 25_997),
+    (1_000_000, 78_498),
+    (3_000_000, 216_816),
+    (10_000_000, 664_579),
+    (30_000_000, 1_857_859),
+    (100_000_000, 5_761_455),
+    (300_000_000, 16_252_325),
+    (1_000_000_000, 50_847_534),
+    (3_000_000_000, 144_449_537),
+    (1 << 32, 203_280_221),
+    (10_000_000_000, 455_052_511),
+    (100_000_000_000, 4_118_054_813),
+    (1_000_000_000_000, 37_607_912_018),
+    (10_000_000_000_000, 346_065_536_839),
+    (100_000_000_000_000, 3_204_941_750_802),
+    (1_000_000_000_000_000, 29_844_570_422_669),
+    (10_000_000_000_000_000, 279_238_341_033_925),
+    (100_000_000_000_000_000, 2_623_557_157_654_233),
+    (1_000_000_000_000_000_000, 24_739_954_287_740_860),
+    (10_000_000_000_000_000_000, 234_057_667_276_344_607),
+    (u64::MAX, 425_656_284_035_217_743),
+];
+
+/// Returns the number of primes below `max` if it is in the built-in table.
+///
+/// The table has the powers of ten up to 10^19, three times the powers of ten up to 3e9 (the usual
+/// benchmark sizes), 2^32 and `u64::MAX`.
+pub fn known(max: u64) -> Option<u64> {
+    KNOWN
+        .iter()
+        .find(|&&(known_max, _)| known_max == max)
+        .map(|&(_, count)| count)
+}
+
+/// Returns the number of primes `<= n`, using the Lucy Hedgehog variant of Legendre's formula.
+///
+/// Needs O(n^(3/4)) time and O(sqrt(n)) memory, e.g. a few seconds for n = 1e12.
+pub fn prime_pi(n: u64) -> u64 {
+    if n < 2 {
+        return 0;
+    }
+    let r = n.isqrt() as usize;
+    // small[v] is the count of numbers in 2..=v not crossed off yet, large[i] the same for n / i
+    let mut small: Vec<u64> = (0..=r as u64).map(|v| v.saturating_sub(1)).collect();
+    let mut large: Vec<u64> = (0..=r as u64)
+        .map(|i| n.checked_div(i).map_or(0, |q| q - 1))
+        .collect();
+
+    for p in 2..=r {
+        if small[p] == small[p - 1] {
+            continue; // not a prime
+        }
+        let primes_below_p = small[p - 1];
+        let p_squared = p as u64 * p as u64;
+        let last = (r as u64).min(n / p_squared) as usize;
+        for i in 1..=last {
+            let d = i as u64 * p as u64;
+            let count = if d <= r as u64 {
+                large[d as usize]
+            } else {
+                small[(n / d) as usize]
+            };
+            large[i] -= count - primes_below_p;
+        }
+        for v in (p_squared as usize..=r).rev() {
+            small[v] -= small[v / p] - primes_below_p;
+        }
+    }
+    large[1]
+}
+
+/// Returns the number of primes below `max`, from the table if possible, otherwise calculated
+/// with [`prime_pi()`].
+pub fn count_below(max: u64) -> u64 {
+    known(max).unwrap_or_else(|| prime_pi(max.saturating_sub(1)))
+}
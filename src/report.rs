@@ -0,0 +1,184 @@
+//! Benchmark results and writers for JSON Lines and CSV.
+
+use crate::harness::Stats;
+use crate::hw;
+use std::fs;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// The machine a benchmark ran on.
+#[derive(Clone, Debug)]
+pub struct HostInfo {
+    pub hostname: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub cpus: usize,
+}
+
+impl HostInfo {
+    pub fn detect() -> HostInfo {
+        let hostname = fs::read_to_string("/proc/sys/kernel/hostname")
+            .or_else(|_| fs::read_to_string("/etc/hostname"))
+            .map(|s| s.trim().to_string())
+            .or_else(|_| std::env::var("HOSTNAME"))
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+        HostInfo {
+            hostname,
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            cpus: hw::cores(),
+        }
+    }
+}
+
+/// The timed runs of one algorithm with one set of tunables.
+#[derive(Clone, Debug)]
+pub struct BenchResult {
+    pub algorithm: String,
+    pub max: u64,
+    pub threads: Option<usize>,
+    pub page_size: Option<usize>,
+    pub stats: Stats,
+    pub primes: u64,
+    /// `true` if `primes` matches a known prime count for `max`.
+    pub verified: bool,
+    pub host: HostInfo,
+}
+
+const CSV_HEADER: [&str; 17] = [
+    "algorithm",
+    "max",
+    "threads",
+    "page_size",
+    "elapsed_ms",
+    "runs",
+    "min_ms",
+    "mean_ms",
+    "stddev_ms",
+    "ci95_ms",
+    "outliers",
+    "primes",
+    "verified",
+    "hostname",
+    "os",
+    "arch",
+    "cpus",
+];
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_option(value: Option<usize>) -> String {
+    value.map_or("null".to_string(), |v| v.to_string())
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn csv_option(value: Option<usize>) -> String {
+    value.map_or(String::new(), |v| v.to_string())
+}
+
+impl BenchResult {
+    /// The median of the timed runs in milliseconds.
+    pub fn elapsed_ms(&self) -> f64 {
+        ms(self.stats.median)
+    }
+
+    /// Returns the result as a single line JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"algorithm\":{},\"max\":{},\"threads\":{},\"page_size\":{},\"elapsed_ms\":{:.3},\
+             \"runs\":{},\"min_ms\":{:.3},\"mean_ms\":{:.3},\"stddev_ms\":{:.3},\"ci95_ms\":{:.3},\
+             \"outliers\":{},\"primes\":{},\"verified\":{},\"host\":{{\"hostname\":{},\"os\":{},\"arch\":{},\
+             \"cpus\":{}}}}}",
+            json_string(&self.algorithm),
+            self.max,
+            json_option(self.threads),
+            json_option(self.page_size),
+            self.elapsed_ms(),
+            self.stats.runs,
+            ms(self.stats.min),
+            ms(self.stats.mean),
+            ms(self.stats.stddev),
+            ms(self.stats.ci95),
+            self.stats.outliers,
+            self.primes,
+            self.verified,
+            json_string(&self.host.hostname),
+            json_string(self.host.os),
+            json_string(self.host.arch),
+            self.host.cpus
+        )
+    }
+
+    /// Returns the result as a CSV record in the column order of [`csv_header()`].
+    pub fn to_csv(&self) -> String {
+        [
+            csv_field(&self.algorithm),
+            self.max.to_string(),
+            csv_option(self.threads),
+            csv_option(self.page_size),
+            format!("{:.3}", self.elapsed_ms()),
+            self.stats.runs.to_string(),
+            format!("{:.3}", ms(self.stats.min)),
+            format!("{:.3}", ms(self.stats.mean)),
+            format!("{:.3}", ms(self.stats.stddev)),
+            format!("{:.3}", ms(self.stats.ci95)),
+            self.stats.outliers.to_string(),
+            self.primes.to_string(),
+            self.verified.to_string(),
+            csv_field(&self.host.hostname),
+            csv_field(self.host.os),
+            csv_field(self.host.arch),
+            self.host.cpus.to_string(),
+        ]
+        .join(",")
+    }
+}
+
+pub fn csv_header() -> String {
+    CSV_HEADER.join(",")
+}
+
+/// Writes one JSON object per line.
+pub fn write_jsonl(w: &mut impl Write, results: &[BenchResult]) -> io::Result<()> {
+    for result in results {
+        writeln!(w, "{}", result.to_json())?;
+    }
+    Ok(())
+}
+
+/// Writes a header line followed by one record per result.
+pub fn write_csv(w: &mut impl Write, results: &[BenchResult]) -> io::Result<()> {
+    writeln!(w, "{}", csv_header())?;
+    for result in results {
+        writeln!(w, "{}", result.to_csv())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Distribution of the slices of the counting sieves over the worker threads.
+//!
+//! The slices cost about the same: a base prime `p` crosses off about `len / p` bits in every
+//! slice it reaches, later slices only add the few base primes whose squares fall into them, and
+//! the last slice is usually shorter. With [`Schedule::Static`] thread `t` takes the slices `t`,
+//! `t + threads`, `t + 2 * threads` and so on, so a thread that is slowed down, e.g. by sharing a
+//! core, holds up the end of the run. With [`Schedule::Dynamic`] the threads take the next `chunk`
+//! slices from a shared atomic counter whenever they are done, until none are left.
+//!
+//! The counting sieves take the schedule like they take a thread count, and return the [`Load`]
+//! of their run next to the count: how long each thread was busy and idle.
+
+use crate::pool::Executor;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How the slices are handed out to the threads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// Thread `t` of `threads` takes every `threads`-th slice starting with slice `t`.
+    Static,
+    /// The threads take `chunk` slices at a time from a shared counter.
+    Dynamic { chunk: usize },
+}
+
+impl Default for Schedule {
+    fn default() -> Schedule {
+        Schedule::Dynamic { chunk: 1 }
+    }
+}
+
+/// The time one thread spent on its slices.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadLoad {
+    pub slices: usize,
+    /// Wall time spent in the slices, with more threads than cores including the time the
+    /// thread was not scheduled.
+    pub busy: Duration,
+    /// The rest of the run: waiting for a worker, between slices and after the last slice.
+    pub idle: Duration,
+}
+
+/// The per-thread busy and idle times of one run.
+#[derive(Clone, Debug, Default)]
+pub struct Load {
+    pub wall: Duration,
+    pub threads: Vec<ThreadLoad>,
+}
+
+impl Load {
+    /// Returns the longest busy time divided by the mean busy time, 1.0 for a perfect balance.
+    pub fn imbalance(&self) -> f64 {
+        let busy: Vec<f64> = self.threads.iter().map(|t| t.busy.as_secs_f64()).collect();
+        let mean = busy.iter().sum::<f64>() / busy.len().max(1) as f64;
+        if mean == 0.0 {
+            return 1.0;
+        }
+        busy.iter().copied().fold(0.0, f64::max) / mean
+    }
+}
+
+impl fmt::Display for Load {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "load: wall {:?}, imbalance {:.2}",
+            self.wall,
+            self.imbalance()
+        )?;
+        for (t, load) in self.threads.iter().enumerate() {
+            writeln!(
+                f,
+                "  thread {:3}: slices: {:6}, busy: {:>10.3?}, idle: {:>10.3?}",
+                t, load.slices, load.busy, load.idle
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `f(state, slice)` for the slices `0..slices` on `threads` threads of `executor`, handed
+/// out by `schedule`, and returns the results in the order of the slices with the load of the run.
+///
+/// Every thread creates its state once with `init`, e.g. a buffer it reuses for all its slices.
+pub fn run<S, R, I, F>(
+    schedule: Schedule,
+    executor: Executor,
+    slices: usize,
+    threads: usize,
+    init: I,
+    f: F,
+) -> (Vec<R>, Load)
+where
+    R: Send + 'static,
+    I: Fn() -> S + Send + Sync + 'static,
+    F: Fn(&mut S, usize) -> R + Send + Sync + 'static,
+{
+    let threads = threads.clamp(1, slices.max(1));
+    let workers = executor.workers(threads);
+    let next = Arc::new(AtomicUsize::new(0));
+    let init = Arc::new(init);
+    let f = Arc::new(f);
+
+    let time_start = Instant::now();
+    let workers: Vec<_> = (0..threads)
+        .map(|t| {
+            let next = next.clone();
+            let init = init.clone();
+            let f = f.clone();
+            workers.submit(move || {
+                let mut state = init();
+                let mut load = ThreadLoad::default();
+                let mut results = vec![];
+                let mut sieve = |slice| {
+                    let time_slice = Instant::now();
+                    results.push((slice, f(&mut state, slice)));
+                    load.busy += time_slice.elapsed();
+                    load.slices += 1;
+                };
+                match schedule {
+                    Schedule::Static => (t..slices).step_by(threads).for_each(&mut sieve),
+                    Schedule::Dynamic { chunk } => {
+                        let chunk = chunk.max(1);
+                        loop {
+                            let first = next.fetch_add(chunk, Ordering::Relaxed);
+                            if first >= slices {
+                                break;
+                            }
+                            (first..(first + chunk).min(slices)).for_each(&mut sieve);
+                        }
+                    }
+                }
+                (results, load)
+            })
+        })
+        .collect();
+
+    let mut results = vec![];
+    let mut loads = vec![];
+    for worker in workers {
+        let (mut worker_results, load) = worker.join();
+        results.append(&mut worker_results);
+        loads.push(load);
+    }
+    let wall = time_start.elapsed();
+    for load in &mut loads {
+        load.idle = wall.saturating_sub(load.busy);
+    }
+    results.sort_unstable_by_key(|&(slice, _)| slice);
+    let results = results.into_iter().map(|(_, result)| result).collect();
+    (
+        results,
+        Load {
+            wall,
+            threads: loads,
+        },
+    )
+}
@@ -0,0 +1,91 @@
+//! Segmented sieve of the odd numbers, each thread reusing one cache-sized buffer.
+//!
+//! Unlike [`recursive_primes_p17()`](crate::sieve::recursive_primes_p17), which gives every thread
+//! a freshly allocated slice of `max / thread_count / 8` numbers, the workers here sieve many small
+//! segments one after another in the same buffer, so the bits being crossed off stay in the cache.
+//! The segments are handed out to the threads by the [`schedule`].
+
+use crate::hw::CacheSizes;
+use crate::packed_bits::PackedBits;
+use crate::pool::Executor;
+use crate::schedule::{self, Load, Schedule};
+use crate::sieve::{odd_base_primes, odd_sieve, sieve_odd_slice_into};
+
+/// A segmented sieve, the bits are laid out like [`odd_sieve()`], bit `i` standing for `2 * i + 1`.
+#[derive(Clone, Copy, Debug)]
+pub struct SegmentedSieve {
+    pub threads: usize,
+    /// Size of the buffer of each thread in bytes, one segment covers `16 * segment_size` numbers.
+    pub segment_size: usize,
+    pub executor: Executor,
+    pub schedule: Schedule,
+}
+
+impl SegmentedSieve {
+    /// Runs on the [`Executor::Pool`] with the [`Schedule::default()`] unless changed.
+    pub fn new(threads: usize, segment_size: usize) -> SegmentedSieve {
+        SegmentedSieve {
+            threads,
+            segment_size,
+            executor: Executor::default(),
+            schedule: Schedule::default(),
+        }
+    }
+
+    /// Uses [`CacheSizes::segment_size()`] for the buffers.
+    pub fn for_machine(threads: usize, caches: CacheSizes) -> SegmentedSieve {
+        SegmentedSieve::new(threads, caches.segment_size())
+    }
+
+    /// Returns the `max / 2` bits of the odd numbers below `max`, like
+    /// [`odd_primes_p18()`](crate::sieve::odd_primes_p18).
+    pub fn primes(&self, max: usize) -> PackedBits {
+        let mut result = PackedBits::new_set(0, true);
+        for mut segment in self.run(max, |segment| segment.clone()).0 {
+            result.append(&mut segment);
+        }
+        result
+    }
+
+    /// Returns the number of primes below `max`, only keeping the count of each segment, and the
+    /// load of the threads.
+    pub fn count(&self, max: usize) -> (u64, Load) {
+        if max < 3 {
+            return (0, Load::default());
+        }
+        let (counts, load) = self.run(max, |segment| segment.count_ones(segment.len()) as u64);
+        // 2 is the only even prime
+        (counts.iter().sum::<u64>() + 1, load)
+    }
+
+    // sieves the segments and returns f of each in the order of the segments, and the load
+    fn run<R, F>(&self, max: usize, f: F) -> (Vec<R>, Load)
+    where
+        R: Send + 'static,
+        F: Fn(&PackedBits) -> R + Send + Sync + 'static,
+    {
+        let len = max / 2;
+        let segment_bits = (self.segment_size * 8).max(64);
+        let segments = len.div_ceil(segment_bits);
+
+        let base_primes = odd_base_primes(&odd_sieve((max as f64).sqrt() as u64 + 1));
+        schedule::run(
+            self.schedule,
+            self.executor,
+            segments,
+            self.threads,
+            move || PackedBits::new_set(segment_bits, true),
+            move |buffer, segment| {
+                let start = segment * segment_bits;
+                let end = (start + segment_bits).min(len);
+                // the short last segment shrinks the buffer, a full one after it needs the bits back
+                if buffer.len() < end - start {
+                    *buffer = PackedBits::new_set(segment_bits, true);
+                }
+                buffer.truncate(end - start);
+                sieve_odd_slice_into(buffer, &base_primes, start);
+                f(buffer)
+            },
+        )
+    }
+}
@@ -0,0 +1,699 @@
+//! Sieves of Eratosthenes, single and multithreaded.
+//!
+//! All sieves return one entry per number, `true` (or a set bit) meaning prime. The odd sieves
+//! only have entries for the odd numbers, the wheel sieves for the numbers coprime to the wheel's
+//! modulus.
+
+use crate::packed_bits::{PackedBits, Word};
+use crate::pool::Executor;
+use crate::schedule::{self, Load, Schedule};
+use crate::wheel::Wheel;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Sieve of Eratosthenes, single thread, one `bool` per number.
+///
+/// Returns `max + 1` entries, i.e. including `max` itself.
+pub fn sieve(max: u64) -> Vec<bool> {
+    let mut primes: Vec<bool> = vec![true; (max + 1) as usize];
+    primes[0] = false;
+    if max >= 1 {
+        primes[1] = false;
+    }
+    let mut first_prime: u64 = 2;
+    while first_prime * first_prime <= max {
+        for i in (first_prime * 2..=max).step_by(first_prime as usize) {
+            primes[i as usize] = false;
+        }
+        first_prime += 1;
+        while first_prime < max && !primes[first_prime as usize] {
+            first_prime += 1;
+        }
+    }
+    primes
+}
+
+// sieve of the numbers 0..max, exactly max entries
+fn sieve_below(max: usize) -> Vec<bool> {
+    if max == 0 {
+        return vec![];
+    }
+    sieve(max as u64 - 1)
+}
+
+/// Multithreaded sieve, splitting the range into `thread_count + 1` slices.
+///
+/// The first slice is sieved recursively, the others in one thread of `executor` each. Returns at least `max`
+/// entries, the numbers from `max` onwards are only sieved up to the slice boundary.
+pub fn recursive_primes_p12(max: usize, thread_count: usize, executor: Executor) -> Vec<bool> {
+    let slice_size = (max + thread_count) / (thread_count + 1);
+    // the primes below slice_size have to cover sqrt(max)
+    if max <= 100 || slice_size <= thread_count {
+        return sieve_below(max);
+    }
+
+    let mut small_primes = recursive_primes_p12(slice_size, thread_count, executor);
+    while small_primes.len() > slice_size {
+        let _ = small_primes.pop();
+    }
+    let workers = executor.workers(thread_count);
+    let mut threads = vec![];
+    let small_primes_arc = Arc::new(small_primes);
+    for thread_idx in 1..=thread_count {
+        let start = thread_idx * slice_size;
+        let small_primes_clone = small_primes_arc.clone();
+        let result = workers.submit(move || {
+            let mut v = vec![true; slice_size];
+            for (idx, p) in small_primes_clone.iter().enumerate() {
+                if *p {
+                    let start_multiple = start.div_ceil(idx) * idx;
+                    let idx_multiple = start_multiple - start;
+                    for i in (idx_multiple..slice_size).step_by(idx) {
+                        v[i] = false;
+                    }
+                }
+            }
+
+            v
+        });
+        threads.push(result);
+    }
+    let mut result = small_primes_arc.as_slice().to_vec();
+    for thread in threads {
+        let mut v = thread.join();
+        result.append(&mut v);
+    }
+
+    result
+}
+
+/// Like [`recursive_primes_p12()`], but the slices are aligned to multiples of `init_size`.
+pub fn recursive_primes_p12a(
+    max: usize,
+    thread_count: usize,
+    init_size: usize,
+    executor: Executor,
+) -> Vec<bool> {
+    let slice_size = (max + thread_count) / (thread_count + 1);
+    let slice_size = (slice_size + init_size) / init_size * init_size;
+    // the primes below slice_size have to cover sqrt(max)
+    if max <= init_size || slice_size >= max || slice_size <= thread_count {
+        return sieve_below(max);
+    }
+    let mut small_primes = if slice_size * slice_size <= max {
+        sieve(slice_size as u64 - 1)
+    } else {
+        recursive_primes_p12a(slice_size, thread_count, init_size, executor)
+    };
+    while small_primes.len() > slice_size {
+        let _ = small_primes.pop();
+    }
+    let workers = executor.workers(thread_count);
+    let mut threads = vec![];
+    let small_primes_arc = Arc::new(small_primes);
+    for thread_idx in 1..=thread_count {
+        let start = thread_idx * slice_size;
+        let small_primes_clone = small_primes_arc.clone();
+        let result = workers.submit(move || {
+            let mut v = vec![true; slice_size];
+            for (idx, p) in small_primes_clone.iter().enumerate() {
+                if *p {
+                    let start_multiple = start.div_ceil(idx) * idx;
+                    let idx_multiple = start_multiple - start;
+                    for i in (idx_multiple..slice_size).step_by(idx) {
+                        v[i] = false;
+                    }
+                }
+            }
+
+            v
+        });
+        threads.push(result);
+    }
+    let mut result = small_primes_arc.as_slice().to_vec();
+    for thread in threads {
+        let mut v = thread.join();
+        result.append(&mut v);
+    }
+
+    result
+}
+
+/// Multithreaded sieve of the numbers `0..max`, returns exactly `max` entries.
+///
+/// The primes up to `sqrt(max)` are sieved recursively, the rest is split into slices of a multiple
+/// of `page_size`, sieved in batches of `thread_count` jobs on `executor`.
+pub fn recursive_primes_p16(
+    max: usize,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+) -> Vec<bool> {
+    if max <= page_size {
+        return sieve_below(max);
+    }
+
+    let small_primes = if page_size * page_size > max {
+        sieve(page_size as u64 - 1)
+    } else {
+        let slice_size = (max as f64).sqrt().trunc() as usize;
+        let slice_size = (slice_size + page_size) / page_size * page_size;
+        // tiny page sizes round sqrt(max) up to max itself, which would recurse forever
+        if slice_size >= max {
+            return sieve_below(max);
+        }
+        recursive_primes_p16(slice_size, thread_count, page_size, executor)
+    };
+
+    let slice_size = max / thread_count / 8;
+    let slice_size = (slice_size + page_size) / page_size * page_size;
+    let slice_size = if slice_size == max {
+        page_size
+    } else {
+        slice_size
+    };
+
+    let mut start = small_primes.len();
+
+    let base_primes: Vec<u32> = (0..small_primes.len())
+        .filter(|&idx| small_primes[idx])
+        .map(|idx| idx as u32)
+        .collect();
+    let base_primes = Arc::new(base_primes);
+    let mut result = small_primes;
+    let workers = executor.workers(thread_count);
+    while start < max {
+        let mut threads = vec![];
+        for _ in 1..=thread_count {
+            if start >= max {
+                break;
+            }
+            let base_primes = base_primes.clone();
+            let end = (start + slice_size).min(max);
+            let result = workers.submit(move || {
+                let mut v = vec![true; end - start];
+                for &p in base_primes.iter() {
+                    let p = p as usize;
+                    let j = start.div_ceil(p) * p;
+                    for i in (j..end).step_by(p) {
+                        v[i - start] = false;
+                    }
+                }
+
+                v
+            });
+            threads.push(result);
+            start = end;
+        }
+        for thread in threads {
+            let mut v = thread.join();
+            result.append(&mut v);
+        }
+    }
+    let _ = result.split_off(max);
+    result
+}
+
+// the primes of a sieve as a list, shared by the threads instead of each scanning the bitmap again
+fn base_primes<W: Word>(small_primes: &PackedBits<W>) -> Vec<u32> {
+    small_primes.iter_ones().map(|p| p as u32).collect()
+}
+
+// like base_primes(), for an odd sieve
+pub(crate) fn odd_base_primes(small_primes: &PackedBits) -> Vec<u32> {
+    small_primes.iter_ones().map(|i| 2 * i as u32 + 1).collect()
+}
+
+/// Sieve of Eratosthenes, single thread, one bit per number.
+///
+/// Returns `max + 1` bits, i.e. including `max` itself.
+pub fn packed_sieve(max: u64) -> PackedBits {
+    packed_sieve_words(max)
+}
+
+/// Like [`packed_sieve()`], but storing the bits in words of type `W`.
+pub fn packed_sieve_words<W: Word>(max: u64) -> PackedBits<W> {
+    let mut primes = PackedBits::new_set(max as usize + 1, true);
+    primes.clear(0);
+    if max >= 1 {
+        primes.clear(1);
+    }
+
+    let mut first_prime: u64 = 2;
+    while first_prime * first_prime <= max {
+        for i in (first_prime * 2..=max).step_by(first_prime as usize) {
+            primes.clear(i as usize);
+        }
+        first_prime += 1;
+        while first_prime < max && !primes.is_set(first_prime as usize) {
+            first_prime += 1;
+        }
+    }
+    primes
+}
+
+// sieve of the numbers 0..max, exactly max bits
+fn packed_sieve_below<W: Word>(max: usize) -> PackedBits<W> {
+    if max == 0 {
+        return PackedBits::new_set(0, true);
+    }
+    packed_sieve_words(max as u64 - 1)
+}
+
+/// Like [`recursive_primes_p16()`], but working on [`PackedBits`] instead of `Vec<bool>`.
+pub fn recursive_primes_p17(
+    max: usize,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+) -> PackedBits {
+    recursive_primes_p17_words(max, thread_count, page_size, executor)
+}
+
+/// Like [`recursive_primes_p17()`], but storing the bits in words of type `W`.
+pub fn recursive_primes_p17_words<W: Word>(
+    max: usize,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+) -> PackedBits<W> {
+    if max <= page_size {
+        return packed_sieve_below(max);
+    }
+
+    let small_primes = if page_size * page_size > max {
+        packed_sieve_words(page_size as u64 - 1)
+    } else {
+        let slice_size = (max as f64).sqrt().trunc() as usize;
+        let slice_size = (slice_size + page_size) / page_size * page_size;
+        // tiny page sizes round sqrt(max) up to max itself, which would recurse forever
+        if slice_size >= max {
+            return packed_sieve_below(max);
+        }
+        recursive_primes_p17_words(slice_size, thread_count, page_size, executor)
+    };
+
+    let slice_size = max / thread_count / 8;
+    let slice_size = (slice_size + page_size) / page_size * page_size;
+    let slice_size = if slice_size == max {
+        page_size
+    } else {
+        slice_size
+    };
+
+    let mut start = small_primes.len();
+
+    let base_primes = Arc::new(base_primes(&small_primes));
+    let mut result = small_primes;
+    let workers = executor.workers(thread_count);
+    while start < max {
+        let mut threads = vec![];
+        for _ in 1..=thread_count {
+            if start >= max {
+                break;
+            }
+            let base_primes = base_primes.clone();
+            let end = (start + slice_size).min(max);
+            let result = workers.submit(move || {
+                let mut v = PackedBits::<W>::new_set(end - start, true);
+                for &p in base_primes.iter() {
+                    let p = p as usize;
+                    let j = start.div_ceil(p) * p;
+                    for i in (j..end).step_by(p) {
+                        v.clear(i - start);
+                    }
+                }
+
+                v
+            });
+            threads.push(result);
+            start = end;
+        }
+        for thread in threads {
+            let mut v = thread.join();
+            result.append(&mut v);
+        }
+    }
+    let _ = result.split_off(max);
+    result
+}
+
+/// Sieve of Eratosthenes on the odd numbers only, single thread, one bit per odd number.
+///
+/// Bit `i` stands for `2 * i + 1`, there are `(max + 1) / 2` bits for the odd numbers up to and
+/// including `max`. 2 is left out.
+pub fn odd_sieve(max: u64) -> PackedBits {
+    let mut primes = PackedBits::new_set((max as usize).div_ceil(2), true);
+    if max == 0 {
+        return primes;
+    }
+    primes.clear(0);
+
+    let mut p: u64 = 3;
+    while p * p <= max {
+        if primes.is_set(p as usize / 2) {
+            for i in ((p * p / 2) as usize..primes.len()).step_by(p as usize) {
+                primes.clear(i);
+            }
+        }
+        p += 2;
+    }
+    primes
+}
+
+// clears the odd multiples of the odd base_primes in the bits start..end of an odd sieve
+fn sieve_odd_slice(base_primes: &[u32], start: usize, end: usize) -> PackedBits {
+    let mut v = PackedBits::new_set(end - start, false);
+    sieve_odd_slice_into(&mut v, base_primes, start);
+    v
+}
+
+// like sieve_odd_slice() for the bits start..start + v.len(), in a buffer the caller reuses
+pub(crate) fn sieve_odd_slice_into(v: &mut PackedBits, base_primes: &[u32], start: usize) {
+    let end = start + v.len();
+    v.set_range(0..v.len());
+    if start == 0 {
+        v.clear(0);
+    }
+    for &p in base_primes {
+        let p = p as usize;
+        // p * p is the first multiple not crossed off by a smaller prime
+        let first = p * p / 2;
+        if first >= end {
+            break;
+        }
+        let j = if first >= start {
+            first
+        } else {
+            first + (start - first).div_ceil(p) * p
+        };
+        for k in (j..end).step_by(p) {
+            v.clear(k - start);
+        }
+    }
+}
+
+/// Multithreaded sieve on the odd numbers below `max`, returns `max / 2` bits like
+/// [`odd_sieve()`].
+///
+/// Works like [`recursive_primes_p17()`] with half the bits: the odd primes up to `sqrt(max)` are
+/// sieved first, then the bits are split into slices of a multiple of `page_size`, sieved in
+/// batches of `thread_count` threads.
+pub fn odd_primes_p18(
+    max: usize,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+) -> PackedBits {
+    let len = max / 2;
+    if max <= page_size {
+        return if max == 0 {
+            odd_sieve(0)
+        } else {
+            odd_sieve(max as u64 - 1)
+        };
+    }
+
+    let small_primes = odd_sieve((max as f64).sqrt() as u64 + 1);
+    let base_primes = Arc::new(odd_base_primes(&small_primes));
+
+    let slice_size = len / thread_count / 8;
+    let slice_size = (slice_size + page_size) / page_size * page_size;
+
+    let mut start = 0;
+    let mut result = PackedBits::new_set(0, true);
+    let workers = executor.workers(thread_count);
+    while start < len {
+        let mut threads = vec![];
+        for _ in 1..=thread_count {
+            if start >= len {
+                break;
+            }
+            let base_primes = base_primes.clone();
+            let end = (start + slice_size).min(len);
+            let result = workers.submit(move || sieve_odd_slice(&base_primes, start, end));
+            threads.push(result);
+            start = end;
+        }
+        for thread in threads {
+            let mut v = thread.join();
+            result.append(&mut v);
+        }
+    }
+    result
+}
+
+// clears the multiples of base_primes in the wheel turns first_turn..last_turn, one bit per residue
+fn sieve_wheel_slice(
+    wheel: &Wheel,
+    base_primes: &[u32],
+    first_turn: usize,
+    last_turn: usize,
+) -> PackedBits {
+    let modulus = wheel.modulus();
+    let spokes = wheel.residues().len();
+    let offset = first_turn * spokes;
+    let mut v = PackedBits::new_set((last_turn - first_turn) * spokes, true);
+    if first_turn == 0 {
+        v.clear(0);
+    }
+    let lo = first_turn * modulus;
+    let hi = last_turn * modulus;
+    for &p in base_primes {
+        let p = p as usize;
+        if p * p >= hi {
+            break;
+        }
+        // the multiples p * m with m coprime to the modulus, one progression per residue of m, each
+        // turn of m moves p * m by p turns
+        let m_min = p.max(lo.div_ceil(p));
+        for &r in wheel.residues() {
+            let m = m_min + (r + modulus - m_min % modulus) % modulus;
+            if p * m >= hi {
+                continue;
+            }
+            let first = wheel.index(p * m).unwrap() - offset;
+            for i in (first..v.len()).step_by(p * spokes) {
+                v.clear(i);
+            }
+        }
+    }
+    v
+}
+
+// the primes up to the square root of the wheel's end, except the wheel's own primes
+fn wheel_base_primes(wheel: &Wheel, turns: usize) -> Vec<u32> {
+    let sqrt = ((turns * wheel.modulus()) as f64).sqrt() as u64 + 1;
+    odd_base_primes(&odd_sieve(sqrt))
+        .into_iter()
+        .filter(|&p| !wheel.modulus().is_multiple_of(p as usize))
+        .collect()
+}
+
+/// Multithreaded wheel sieve of the numbers below `max`, one bit per number coprime to the
+/// modulus of `wheel`, i.e. `wheel.count_below(max)` bits with bit `i` standing for
+/// `wheel.value(i)`.
+///
+/// The primes up to `sqrt(max)` are sieved with [`odd_sieve()`], the rest is split into slices of
+/// whole wheel turns of about a multiple of `page_size` bits, sieved in batches of `thread_count`
+/// threads. The wheel's primes themselves are not included.
+pub fn wheel_primes_p19(
+    max: usize,
+    wheel: &Wheel,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+) -> PackedBits {
+    let len = wheel.count_below(max);
+    let spokes = wheel.residues().len();
+    let turns = max.div_ceil(wheel.modulus());
+
+    let base_primes = Arc::new(wheel_base_primes(wheel, turns));
+    let wheel = Arc::new(wheel.clone());
+
+    let slice_size = len / thread_count / 8;
+    let slice_size = (slice_size + page_size) / page_size * page_size;
+    let slice_turns = slice_size.div_ceil(spokes);
+
+    let mut start = 0;
+    let mut result = PackedBits::new_set(0, true);
+    let workers = executor.workers(thread_count);
+    while start < turns {
+        let mut threads = vec![];
+        for _ in 1..=thread_count {
+            if start >= turns {
+                break;
+            }
+            let base_primes_clone = base_primes.clone();
+            let wheel_clone = wheel.clone();
+            let end = (start + slice_turns).min(turns);
+            let result = workers
+                .submit(move || sieve_wheel_slice(&wheel_clone, &base_primes_clone, start, end));
+            threads.push(result);
+            start = end;
+        }
+        for thread in threads {
+            let mut v = thread.join();
+            result.append(&mut v);
+        }
+    }
+    result.truncate(len);
+    result
+}
+
+// upper bound for the slices of the count-only sieves in bits, so the memory stays at
+// thread_count * 256K no matter how large max is
+const COUNT_SLICE_BITS: usize = 1 << 21;
+
+// the slice size of the multithreaded sieves, a multiple of page_size, capped for counting only
+fn count_slice_size(len: usize, thread_count: usize, page_size: usize) -> usize {
+    let slice_size = len / thread_count / 8;
+    let slice_size = (slice_size + page_size) / page_size * page_size;
+    slice_size.min(COUNT_SLICE_BITS.div_ceil(page_size) * page_size)
+}
+
+// sums count(start, end) over the slices of start..end, handed out to the threads by schedule
+fn sum_slices(
+    range: Range<usize>,
+    slice_size: usize,
+    thread_count: usize,
+    executor: Executor,
+    schedule: Schedule,
+    count: impl Fn(usize, usize) -> u64 + Send + Sync + 'static,
+) -> (u64, Load) {
+    let slices = range.len().div_ceil(slice_size);
+    let (counts, load) = schedule::run(
+        schedule,
+        executor,
+        slices,
+        thread_count,
+        || (),
+        move |_, slice| {
+            let start = range.start + slice * slice_size;
+            count(start, (start + slice_size).min(range.end))
+        },
+    );
+    (counts.iter().sum(), load)
+}
+
+/// Returns the number of primes below `max` like [`recursive_primes_p17()`], but without keeping
+/// the bits: each thread sieves its slices one at a time and only returns their counts.
+///
+/// The slices are at most 2M bits, so the memory is `O(sqrt(max) + thread_count * 256K)`. They are
+/// handed out to the threads by `schedule`, the [`Load`] of the threads is returned with the count,
+/// empty if `max` is too small to be split.
+pub fn count_primes_p17(
+    max: usize,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+    schedule: Schedule,
+) -> (u64, Load) {
+    count_primes_p17_words::<u64>(max, thread_count, page_size, executor, schedule)
+}
+
+/// Like [`count_primes_p17()`], but storing the bits in words of type `W`.
+pub fn count_primes_p17_words<W: Word>(
+    max: usize,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+    schedule: Schedule,
+) -> (u64, Load) {
+    let small_primes: PackedBits<W> = packed_sieve_words((max as f64).sqrt() as u64 + 1);
+    if max <= page_size || small_primes.len() >= max {
+        let primes: PackedBits<W> = packed_sieve_below(max);
+        return (primes.count_ones(primes.len()) as u64, Load::default());
+    }
+    let start = small_primes.len();
+    let small = small_primes.count_ones(start) as u64;
+    let base_primes = base_primes(&small_primes);
+    let slice_size = count_slice_size(max - start, thread_count, page_size);
+    let (sum, load) = sum_slices(
+        start..max,
+        slice_size,
+        thread_count,
+        executor,
+        schedule,
+        move |start, end| {
+            let mut v: PackedBits<W> = PackedBits::new_set(end - start, true);
+            for &p in &base_primes {
+                let p = p as usize;
+                let j = start.div_ceil(p) * p;
+                for i in (j..end).step_by(p) {
+                    v.clear(i - start);
+                }
+            }
+            v.count_ones(v.len()) as u64
+        },
+    );
+    (small + sum, load)
+}
+
+/// Returns the number of primes below `max` like [`odd_primes_p18()`], counting each slice like
+/// [`count_primes_p17()`].
+pub fn count_primes_p18(
+    max: usize,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+    schedule: Schedule,
+) -> (u64, Load) {
+    if max < 3 {
+        return (0, Load::default());
+    }
+    // 2 is the only even prime
+    if max <= page_size {
+        let primes = odd_sieve(max as u64 - 1);
+        return (primes.count_ones(primes.len()) as u64 + 1, Load::default());
+    }
+    let base_primes = odd_base_primes(&odd_sieve((max as f64).sqrt() as u64 + 1));
+    let len = max / 2;
+    let slice_size = count_slice_size(len, thread_count, page_size);
+    let (sum, load) = sum_slices(
+        0..len,
+        slice_size,
+        thread_count,
+        executor,
+        schedule,
+        move |start, end| {
+            let v = sieve_odd_slice(&base_primes, start, end);
+            v.count_ones(v.len()) as u64
+        },
+    );
+    (sum + 1, load)
+}
+
+/// Returns the number of primes below `max` like [`wheel_primes_p19()`], counting each slice like
+/// [`count_primes_p17()`]. The wheel's primes are included.
+pub fn count_primes_p19(
+    max: usize,
+    wheel: &Wheel,
+    thread_count: usize,
+    page_size: usize,
+    executor: Executor,
+    schedule: Schedule,
+) -> (u64, Load) {
+    let len = wheel.count_below(max);
+    let spokes = wheel.residues().len();
+    let turns = max.div_ceil(wheel.modulus());
+    let base_primes = wheel_base_primes(wheel, turns);
+
+    let small = wheel.primes().iter().filter(|&&p| p < max).count() as u64;
+
+    let slice_turns = count_slice_size(len, thread_count, page_size).div_ceil(spokes);
+    let wheel = wheel.clone();
+    let (sum, load) = sum_slices(
+        0..turns,
+        slice_turns,
+        thread_count,
+        executor,
+        schedule,
+        move |start, end| {
+            let v = sieve_wheel_slice(&wheel, &base_primes, start, end);
+            // the last turn goes past max
+            v.count_ones(v.len().min(len - start * spokes)) as u64
+        },
+    );
+    (small + sum, load)
+}
@@ -0,0 +1,131 @@
+//! Smallest prime factor table, to factor every number up to a limit in `O(log n)`.
+//!
+//! [`SpfSieve::new()`] is the linear sieve: every composite `i * p` is written exactly once, by
+//! its smallest prime factor `p`. [`SpfSieve::with_threads()`] sieves segments of the table in
+//! parallel instead, each one crossing off the multiples of the primes up to `sqrt(limit)` that
+//! don't have a smaller factor yet.
+
+use crate::pool::Executor;
+use crate::schedule::{self, Schedule};
+use std::sync::Arc;
+
+// entries per segment of the multithreaded sieve, 256K of u32s
+const SEGMENT_LEN: usize = 1 << 16;
+
+/// The smallest prime factor of every number from 0 to `limit`, 4 bytes per number.
+#[derive(Clone, Debug)]
+pub struct SpfSieve {
+    // spf[n] is the smallest prime factor of n, n itself for primes and 0 for 0 and 1
+    spf: Vec<u32>,
+}
+
+impl SpfSieve {
+    /// Sieves `0..=limit` with the linear sieve on the current thread.
+    pub fn new(limit: u32) -> SpfSieve {
+        let len = limit as usize + 1;
+        let mut spf = vec![0u32; len];
+        let mut primes: Vec<u32> = vec![];
+        for i in 2..len {
+            if spf[i] == 0 {
+                spf[i] = i as u32;
+                primes.push(i as u32);
+            }
+            for &p in &primes {
+                let multiple = i * p as usize;
+                if p > spf[i] || multiple >= len {
+                    break;
+                }
+                spf[multiple] = p;
+            }
+        }
+        SpfSieve { spf }
+    }
+
+    /// Sieves `0..=limit` in segments of 64K numbers on `threads` threads.
+    pub fn with_threads(limit: u32, threads: usize) -> SpfSieve {
+        let len = limit as usize + 1;
+        let base = SpfSieve::new((limit as u64).isqrt() as u32);
+        let primes: Arc<Vec<u32>> = Arc::new(base.primes().collect());
+        let segments = len.div_ceil(SEGMENT_LEN);
+        let (spf_segments, _) = schedule::run(
+            Schedule::default(),
+            Executor::default(),
+            segments,
+            threads,
+            || (),
+            move |_, segment| {
+                let start = segment * SEGMENT_LEN;
+                let end = (start + SEGMENT_LEN).min(len);
+                let mut spf = vec![0u32; end - start];
+                // the primes are ascending, the first one to reach a number is its smallest factor
+                for &p in primes.iter() {
+                    let p = p as usize;
+                    if p * p >= end {
+                        break;
+                    }
+                    let first = (p * p).max(start.div_ceil(p) * p);
+                    for m in (first..end).step_by(p) {
+                        if spf[m - start] == 0 {
+                            spf[m - start] = p as u32;
+                        }
+                    }
+                }
+                for (i, factor) in spf.iter_mut().enumerate() {
+                    if *factor == 0 && start + i >= 2 {
+                        *factor = (start + i) as u32;
+                    }
+                }
+                spf
+            },
+        );
+        SpfSieve {
+            spf: spf_segments.concat(),
+        }
+    }
+
+    /// Returns the largest number in the table.
+    pub fn limit(&self) -> u32 {
+        (self.spf.len() - 1) as u32
+    }
+
+    /// Returns the smallest prime factor of `n`, `n` itself for primes and 0 for 0 and 1.
+    ///
+    /// Panics if `n` is larger than the limit.
+    pub fn smallest_factor(&self, n: u32) -> u32 {
+        self.spf[n as usize]
+    }
+
+    /// Panics if `n` is larger than the limit.
+    pub fn is_prime(&self, n: u32) -> bool {
+        n >= 2 && self.spf[n as usize] == n
+    }
+
+    /// Returns the primes up to the limit in ascending order.
+    pub fn primes(&self) -> impl Iterator<Item = u32> + '_ {
+        (2..self.spf.len() as u32).filter(|&n| self.is_prime(n))
+    }
+
+    /// Returns the prime factors of `n` in ascending order with their exponents, like
+    /// [`factor()`](crate::factor::factor), by following the smallest factors.
+    ///
+    /// Panics if `n` is larger than the limit.
+    ///
+    /// ```
+    /// use parallel_primes::spf_sieve::SpfSieve;
+    /// let sieve = SpfSieve::new(1000);
+    /// assert_eq!(sieve.factor(360), [(2, 3), (3, 2), (5, 1)]);
+    /// assert!(sieve.is_prime(997));
+    /// ```
+    pub fn factor(&self, mut n: u32) -> Vec<(u32, u32)> {
+        let mut factors: Vec<(u32, u32)> = vec![];
+        while n > 1 {
+            let p = self.spf[n as usize];
+            match factors.last_mut() {
+                Some((q, exponent)) if *q == p => *exponent += 1,
+                _ => factors.push((p, 1)),
+            }
+            n /= p;
+        }
+        factors
+    }
+}
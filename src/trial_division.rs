@@ -0,0 +1,146 @@
+//! Trial division, with and without memoization of the primes found so far.
+
+/// Trial division by every number up to `sqrt(n)`.
+///
+/// Returns 1 if `n` is prime and 0 otherwise, so the results can be summed up directly.
+pub fn is_prime1(n: u64) -> u64 {
+    if n <= 1 {
+        return 0;
+    }
+    if n <= 3 {
+        return 1;
+    }
+    let mut i: u64 = 2;
+    while i * i <= n {
+        if n.is_multiple_of(i) {
+            return 0;
+        }
+        i += 1;
+    }
+
+    1
+}
+
+/// Trial division by memoized primes.
+///
+/// Starts out with the primes up to 13, callers are expected to continue at 15.
+pub struct Primes3 {
+    primes: Vec<u64>,
+}
+
+impl Default for Primes3 {
+    fn default() -> Primes3 {
+        Primes3::new()
+    }
+}
+
+impl Primes3 {
+    pub fn new() -> Primes3 {
+        Primes3 {
+            primes: vec![2, 3, 5, 7, 11, 13],
+        }
+    }
+
+    /// Returns 1 if `n` is prime and 0 otherwise, memoizing `n` if it is a prime.
+    ///
+    /// Has to be called monotonically with every possible prime, no gaps.
+    /// Specifically, all primes from `0..sqrt(n)` have to be memoized, e.g. by calling it for
+    /// every odd number from 15 to `max`.
+    pub fn to_prime_mut(&mut self, n: u64) -> u64 {
+        if n <= 1 {
+            return 0;
+        };
+        if n > 1 && n <= 3 {
+            self.primes.push(n);
+            return 1;
+        }
+
+        for i in 0.. {
+            let p = self.primes[i];
+            if p * p > n {
+                break;
+            }
+            if n.is_multiple_of(p) {
+                return 0;
+            }
+        }
+        self.primes.push(n);
+
+        1
+    }
+
+    // check if the number is a prime and store it in v if yes
+    // requires that all primes up to sqrt(start) are already stored in self.primes
+    fn is_prime_store(&self, n: u64, v: &mut Vec<u64>) {
+        if n <= 1 {
+            return;
+        };
+        if n > 1 && n <= 3 {
+            v.push(n);
+            return;
+        }
+
+        for i in 0.. {
+            let p = self.primes[i];
+            if p * p > n {
+                break;
+            }
+            if n.is_multiple_of(p) {
+                return;
+            }
+        }
+        v.push(n);
+    }
+
+    /// Returns the primes between `start` and `end` (excl.), `start` has to be odd.
+    ///
+    /// Requires that all primes up to `sqrt(end)` are already memoized.
+    pub fn prime_slice(&self, start: u64, end: u64) -> Vec<u64> {
+        let mut v = vec![];
+        let mut i = start;
+        while i < end {
+            self.is_prime_store(i, &mut v);
+            i += 2;
+        }
+        v
+    }
+
+    /// Appends a slice to the memoized primes, meant to be called after
+    /// [`prime_slice()`](Primes3::prime_slice).
+    pub fn prime_slice_store(&mut self, v: &mut Vec<u64>) {
+        self.primes.append(v);
+    }
+}
+
+fn is_prime_store(n: u64, small_primes: &[u64], v: &mut Vec<u64>) {
+    if n <= 1 {
+        return;
+    };
+    if n > 1 && n <= 3 {
+        v.push(n);
+        return;
+    }
+
+    for p in small_primes {
+        if p * p > n {
+            break;
+        }
+        if n.is_multiple_of(*p) {
+            return;
+        }
+    }
+    v.push(n);
+}
+
+/// Returns the primes between `start` and `end` (excl.), `start` has to be odd.
+///
+/// `small_primes` has to contain all primes up to `sqrt(end)` in ascending order.
+pub fn prime_slice(small_primes: &[u64], start: u64, end: u64) -> Vec<u64> {
+    let mut v = vec![];
+    let mut i = start;
+    while i < end {
+        is_prime_store(i, small_primes, &mut v);
+        i += 2;
+    }
+    v
+}
@@ -0,0 +1,146 @@
+//! Search for the fastest thread count and page size of an algorithm.
+
+use crate::counter::PrimeCounter;
+use crate::harness::{self, HarnessConfig, Stats};
+use crate::hw::CacheSizes;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The thread counts and page sizes of the initial grid.
+#[derive(Clone, Debug)]
+pub struct TuneSpace {
+    pub thread_counts: Vec<usize>,
+    pub page_sizes: Vec<usize>,
+    /// Upper bound for the thread counts tried during refinement.
+    pub max_threads: usize,
+    /// Upper bound for the page sizes tried during refinement.
+    pub max_page_size: usize,
+}
+
+impl TuneSpace {
+    /// Powers of two from 1 to twice the number of cores, plus the number of cores itself, and
+    /// page sizes from 256 up to pages of 128K bytes, capped at the L2 cache size.
+    ///
+    /// `page_size_bits` is the [`PrimeCounter::page_size_bits()`] of the algorithm, so the cap
+    /// is 8 times as large for the sieves counting the page size in bits.
+    pub fn for_machine(cores: usize, caches: CacheSizes, page_size_bits: usize) -> TuneSpace {
+        let max_threads = (2 * cores).max(1);
+        let mut thread_counts = vec![];
+        let mut thread_count = 1;
+        while thread_count <= max_threads {
+            thread_counts.push(thread_count);
+            thread_count *= 2;
+        }
+        if !thread_counts.contains(&cores) {
+            thread_counts.push(cores);
+            thread_counts.sort_unstable();
+        }
+
+        let max_page_bytes = caches.l2.unwrap_or(128 * 1024).clamp(256, 128 * 1024);
+        let max_page_size = max_page_bytes * 8 / page_size_bits.max(1);
+        let mut page_sizes = vec![];
+        let mut page_size = 256;
+        while page_size <= max_page_size {
+            page_sizes.push(page_size);
+            page_size *= 2;
+        }
+
+        TuneSpace {
+            thread_counts,
+            page_sizes,
+            max_threads,
+            max_page_size,
+        }
+    }
+}
+
+/// The fastest configuration found by [`tune()`].
+#[derive(Clone, Copy, Debug)]
+pub struct Tuned {
+    pub threads: usize,
+    pub page_size: usize,
+    pub median: Duration,
+}
+
+// page sizes stay multiples of 64 (and never 0), so the walk moves on a coarse grid instead of
+// chasing measurement noise through page sizes only a few bytes apart
+fn round_page_size(page_size: usize) -> usize {
+    (page_size / 64).max(1) * 64
+}
+
+/// Measures every cell of the grid, then walks from the fastest cell to faster neighbours
+/// (25% more or fewer threads, 25% larger or smaller pages) until none is faster.
+///
+/// `on_result` is called with the threads, page size and statistics of every measured cell.
+/// Returns `None` if `counter` has no thread count or page size to tune.
+pub fn tune(
+    counter: &mut dyn PrimeCounter,
+    max: u64,
+    space: &TuneSpace,
+    harness: HarnessConfig,
+    mut on_result: impl FnMut(usize, usize, &Stats),
+) -> Option<Tuned> {
+    counter.threads()?;
+    counter.page_size()?;
+
+    let mut measured: HashMap<(usize, usize), Duration> = HashMap::new();
+    let mut measure = |counter: &mut dyn PrimeCounter, threads: usize, page_size: usize| {
+        if let Some(median) = measured.get(&(threads, page_size)) {
+            return *median;
+        }
+        counter.set_threads(threads);
+        counter.set_page_size(page_size);
+        let (_, _, stats) = harness::measure(counter, max, harness);
+        on_result(threads, page_size, &stats);
+        measured.insert((threads, page_size), stats.median);
+        stats.median
+    };
+
+    let mut best: Option<Tuned> = None;
+    for &page_size in &space.page_sizes {
+        for &threads in &space.thread_counts {
+            let median = measure(counter, threads, page_size);
+            if best.is_none_or(|best| median < best.median) {
+                best = Some(Tuned {
+                    threads,
+                    page_size,
+                    median,
+                });
+            }
+        }
+    }
+    let mut best = best?;
+
+    loop {
+        let threads = best.threads;
+        let page_size = best.page_size;
+        let neighbours = [
+            ((threads * 3 / 4).max(1), page_size),
+            ((threads * 5).div_ceil(4).min(space.max_threads), page_size),
+            (threads, round_page_size(page_size * 3 / 4)),
+            (
+                threads,
+                round_page_size(page_size * 5 / 4).min(space.max_page_size),
+            ),
+        ];
+        let mut improved = false;
+        for (threads, page_size) in neighbours {
+            let median = measure(counter, threads, page_size);
+            if median < best.median {
+                best = Tuned {
+                    threads,
+                    page_size,
+                    median,
+                };
+                improved = true;
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    counter.set_threads(best.threads);
+    counter.set_page_size(best.page_size);
+    Some(best)
+}
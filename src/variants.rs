@@ -0,0 +1,929 @@
+//! The prime counting algorithms P1 to P20, each implementing [`PrimeCounter`].
+//!
+//! [`P17Word`] is P17 with another word size, `p17-u8` to `p17-u128`.
+
+use crate::counter::PrimeCounter;
+use crate::hw::{self, CacheSizes};
+use crate::packed_bits::Word;
+use crate::pool::Executor;
+use crate::schedule::{Load, Schedule};
+use crate::segmented::SegmentedSieve;
+use crate::sieve::{
+    count_primes_p17, count_primes_p17_words, count_primes_p18, count_primes_p19, packed_sieve,
+    recursive_primes_p12, recursive_primes_p12a, recursive_primes_p16, sieve,
+};
+use crate::trial_division::{is_prime1, prime_slice, Primes3};
+use crate::wheel::Wheel;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+const SMALL_PRIMES: [u64; 6] = [2, 3, 5, 7, 11, 13];
+
+// number of primes below max, for the limits below the starting point of the memoizing variants
+fn count_small(max: u64) -> u64 {
+    SMALL_PRIMES.iter().filter(|&&p| p < max).count() as u64
+}
+
+pub struct P1;
+
+impl PrimeCounter for P1 {
+    fn name(&self) -> &'static str {
+        "p1"
+    }
+
+    fn description(&self) -> &'static str {
+        "naive trial division"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let mut sum = 0;
+        for i in 1..max {
+            sum += is_prime1(i);
+        }
+        sum
+    }
+}
+
+pub struct P2;
+
+impl PrimeCounter for P2 {
+    fn name(&self) -> &'static str {
+        "p2"
+    }
+
+    fn description(&self) -> &'static str {
+        "trial division, step size = 4"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let mut i = 5;
+        let mut sum = count_small(max.min(5)); // two primes below 5
+        while i < max {
+            sum += is_prime1(i);
+            if i + 2 < max {
+                sum += is_prime1(i + 2);
+            }
+            i += 4;
+        }
+        sum
+    }
+}
+
+pub struct P3;
+
+impl PrimeCounter for P3 {
+    fn name(&self) -> &'static str {
+        "p3"
+    }
+
+    fn description(&self) -> &'static str {
+        "memoization"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let mut i = 5;
+        let mut sum = count_small(max.min(5)); // two primes below 5
+        let mut p = Primes3::new();
+        while i < max {
+            sum += p.to_prime_mut(i);
+            i += 2;
+        }
+        sum
+    }
+}
+
+pub struct P4;
+
+impl PrimeCounter for P4 {
+    fn name(&self) -> &'static str {
+        "p4"
+    }
+
+    fn description(&self) -> &'static str {
+        "memoization, step size = 4"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let mut i = 5;
+        let mut sum = count_small(max.min(5)); // two primes below 5
+        let mut p = Primes3::new();
+        while i < max {
+            sum += p.to_prime_mut(i);
+            if i + 2 < max {
+                sum += p.to_prime_mut(i + 2);
+            }
+            i += 4;
+        }
+        sum
+    }
+}
+
+pub struct P5;
+
+impl PrimeCounter for P5 {
+    fn name(&self) -> &'static str {
+        "p5"
+    }
+
+    fn description(&self) -> &'static str {
+        "memoization in slices, preparing for multi threading"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let mut sum = count_small(max); // six primes below 15
+        let mut p = Primes3::new();
+        const STEP_SIZE: u64 = 3000;
+        const THREADS: u64 = 4;
+        let mut last = 15; // primes up to 13 are already stored
+
+        while last < max {
+            let step_to_max = (max - last) / THREADS;
+            let step_root = (last * last - last) / THREADS;
+            let step = step_to_max.min(step_root).clamp(4, STEP_SIZE) & 0xfffffffffffffffe;
+            let mut v = p.prime_slice(last, (last + step).min(max));
+            last += step;
+            sum += v.len() as u64;
+            p.prime_slice_store(&mut v);
+        }
+        sum
+    }
+}
+
+#[derive(Default)]
+pub struct P6 {
+    pub executor: Executor,
+}
+
+impl PrimeCounter for P6 {
+    fn name(&self) -> &'static str {
+        "p6"
+    }
+
+    fn description(&self) -> &'static str {
+        "memoization in 1 thread"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let mut sum = count_small(max); // six primes below 15
+        const STEP_SIZE: u64 = 50_000;
+        const THREADS: u64 = 4;
+        let mut last = 15; // primes up to 13 are already stored
+
+        // RwLock
+        let mut primes = Arc::new(SMALL_PRIMES.to_vec());
+
+        let workers = self.executor.workers(1);
+        while last < max {
+            let step_to_max = (max - last) / THREADS;
+            let step_root = (last * last - last) / THREADS;
+            let step = step_to_max.min(step_root).clamp(4, STEP_SIZE) & 0xfffffffffffffffe;
+            let start = last;
+            let end = (last + step).min(max);
+            let read_primes = primes.clone();
+            let mut result = workers
+                .submit(move || prime_slice(&read_primes, start, end))
+                .join();
+
+            last += step;
+            sum += result.len() as u64;
+            // the job has dropped its reference, so this appends without copying
+            Arc::make_mut(&mut primes).append(&mut result);
+        }
+        sum
+    }
+}
+
+pub struct P7 {
+    pub threads: usize,
+    pub executor: Executor,
+}
+
+impl Default for P7 {
+    fn default() -> P7 {
+        P7 {
+            threads: 32,
+            executor: Executor::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P7 {
+    fn name(&self) -> &'static str {
+        "p7"
+    }
+
+    fn description(&self) -> &'static str {
+        "memoization in threads"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let mut sum = count_small(max); // six primes below 15
+        const STEP_SIZE: u64 = 500_000;
+        let thread_count = self.threads as u64;
+        let mut last = 15; // primes up to 13 are already stored
+
+        // RwLock
+        let mut primes = Arc::new(SMALL_PRIMES.to_vec());
+
+        let workers = self.executor.workers(self.threads);
+        while last < max {
+            let step_to_max = (max - last) / thread_count;
+            let step_root = (last * last - last) / thread_count;
+            let step = step_to_max.min(step_root).clamp(4, STEP_SIZE) & 0xfffffffffffffffe;
+            let mut threads = vec![];
+            for i in 0..thread_count {
+                let start = last + i * step;
+                let end = (last + (i + 1) * step).min(max);
+                let read_primes = primes.clone();
+                let rs = workers.submit(move || prime_slice(&read_primes, start, end));
+                threads.push(rs);
+            }
+
+            // all jobs are joined before appending, so make_mut() finds the only reference
+            let results: Vec<_> = threads.into_iter().map(|t| t.join()).collect();
+            let primes = Arc::make_mut(&mut primes);
+            for mut result in results {
+                sum += result.len() as u64;
+                primes.append(&mut result);
+            }
+
+            last += step * thread_count;
+        }
+        sum
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+}
+
+// copy data less frequently
+pub struct P8 {
+    pub threads: usize,
+    pub step_size: u64,
+    pub executor: Executor,
+}
+
+impl Default for P8 {
+    fn default() -> P8 {
+        P8 {
+            threads: 16,
+            step_size: 50_000_000,
+            executor: Executor::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P8 {
+    fn name(&self) -> &'static str {
+        "p8"
+    }
+
+    fn description(&self) -> &'static str {
+        "memoization in threads, copy data less frequently"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        let thread_count = self.threads as u64;
+        let mut sum = count_small(max); // six primes below 15
+        let mut last = 15; // primes up to 13 are already stored
+
+        // RwLock
+        let mut primes = SMALL_PRIMES.to_vec();
+
+        let workers = self.executor.workers(self.threads);
+        while last < max {
+            let step_to_max = (max - last) / thread_count;
+            let step_root = (last * last - last) / thread_count;
+            let step = step_to_max.min(step_root).clamp(4, self.step_size) & 0xfffffffffffffffe;
+            let mut threads = vec![];
+            let read_primes = Arc::new(primes.clone());
+            for i in 0..thread_count {
+                let start = last + i * step;
+                let end = (last + (i + 1) * step).min(max);
+                let clone = read_primes.clone();
+                let rs = workers.submit(move || prime_slice(&clone, start, end));
+                threads.push(rs);
+            }
+
+            for t in threads {
+                let mut result = t.join();
+                sum += result.len() as u64;
+                primes.append(&mut result);
+            }
+
+            last += step * thread_count;
+        }
+        sum
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+}
+
+pub struct P10;
+
+impl PrimeCounter for P10 {
+    fn name(&self) -> &'static str {
+        "p10"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sieve of Eratosthenes, single thread, Vec<bool>"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        if max < 2 {
+            return 0;
+        }
+        let primes = sieve(max - 1);
+
+        let mut sum = 0;
+        for p in primes {
+            if p {
+                sum += 1
+            };
+        }
+        sum
+    }
+}
+
+pub struct P11;
+
+impl PrimeCounter for P11 {
+    fn name(&self) -> &'static str {
+        "p11"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sieve of Eratosthenes, single thread, packed bits"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        if max < 2 {
+            return 0;
+        }
+        let primes = packed_sieve(max - 1);
+        primes.count_ones(max as usize) as u64
+    }
+}
+
+pub struct P12 {
+    pub threads: usize,
+    pub executor: Executor,
+}
+
+impl Default for P12 {
+    fn default() -> P12 {
+        P12 {
+            threads: 4,
+            executor: Executor::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P12 {
+    fn name(&self) -> &'static str {
+        "p12"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sieve, multithreaded"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        if max < 2 {
+            return 0;
+        }
+        let primes = recursive_primes_p12(max as usize, self.threads, self.executor);
+        primes.iter().take(max as usize).filter(|&&p| p).count() as u64
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+}
+
+// the page size is the init_size of recursive_primes_p12a()
+pub struct P12a {
+    pub threads: usize,
+    pub page_size: usize,
+    pub executor: Executor,
+}
+
+impl Default for P12a {
+    fn default() -> P12a {
+        P12a {
+            threads: 4,
+            page_size: 32 * 1024,
+            executor: Executor::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P12a {
+    fn name(&self) -> &'static str {
+        "p12a"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sieve, multithreaded, aligned"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        if max < 2 {
+            return 0;
+        }
+        let primes =
+            recursive_primes_p12a(max as usize, self.threads, self.page_size, self.executor);
+        primes.iter().take(max as usize).filter(|&&p| p).count() as u64
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.page_size)
+    }
+
+    fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size;
+    }
+}
+
+pub struct P16 {
+    pub threads: usize,
+    pub page_size: usize,
+    pub executor: Executor,
+}
+
+impl Default for P16 {
+    fn default() -> P16 {
+        P16 {
+            threads: 16,
+            page_size: 4096,
+            executor: Executor::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P16 {
+    fn name(&self) -> &'static str {
+        "p16"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sieve, multithreaded, aligned to page size"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        if max < 2 {
+            return 0;
+        }
+        let primes =
+            recursive_primes_p16(max as usize, self.threads, self.page_size, self.executor);
+        let mut sum = 0;
+        for p in primes {
+            if p {
+                sum += 1;
+            };
+        }
+        sum
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.page_size)
+    }
+
+    fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size;
+    }
+}
+
+pub struct P17 {
+    pub threads: usize,
+    pub page_size: usize,
+    pub executor: Executor,
+    pub schedule: Schedule,
+}
+
+impl Default for P17 {
+    fn default() -> P17 {
+        P17 {
+            threads: 16,
+            page_size: 4096,
+            executor: Executor::default(),
+            schedule: Schedule::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P17 {
+    fn name(&self) -> &'static str {
+        "p17"
+    }
+
+    fn description(&self) -> &'static str {
+        "like p16, but working on bits instead of bytes"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        self.count_with_load(max).0
+    }
+
+    fn count_with_load(&self, max: u64) -> (u64, Option<Load>) {
+        if max < 2 {
+            return (0, None);
+        }
+        let (count, load) = count_primes_p17(
+            max as usize,
+            self.threads,
+            self.page_size,
+            self.executor,
+            self.schedule,
+        );
+        (count, Some(load))
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.page_size)
+    }
+
+    fn page_size_bits(&self) -> usize {
+        1
+    }
+
+    fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size;
+    }
+}
+
+// p17 with a different word size of the packed bits, to compare the word sizes against each other
+pub struct P17Word<W: Word> {
+    pub threads: usize,
+    pub page_size: usize,
+    pub executor: Executor,
+    pub schedule: Schedule,
+    word: PhantomData<W>,
+}
+
+impl<W: Word> Default for P17Word<W> {
+    fn default() -> P17Word<W> {
+        P17Word {
+            threads: 16,
+            page_size: 4096,
+            executor: Executor::default(),
+            schedule: Schedule::default(),
+            word: PhantomData,
+        }
+    }
+}
+
+impl<W: Word> PrimeCounter for P17Word<W> {
+    fn name(&self) -> &'static str {
+        match W::BITS {
+            8 => "p17-u8",
+            16 => "p17-u16",
+            32 => "p17-u32",
+            64 => "p17-u64",
+            _ => "p17-u128",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match W::BITS {
+            8 => "like p17, but with u8 words",
+            16 => "like p17, but with u16 words",
+            32 => "like p17, but with u32 words",
+            64 => "same as p17",
+            _ => "like p17, but with u128 words",
+        }
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        self.count_with_load(max).0
+    }
+
+    fn count_with_load(&self, max: u64) -> (u64, Option<Load>) {
+        if max < 2 {
+            return (0, None);
+        }
+        let (count, load) = count_primes_p17_words::<W>(
+            max as usize,
+            self.threads,
+            self.page_size,
+            self.executor,
+            self.schedule,
+        );
+        (count, Some(load))
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.page_size)
+    }
+
+    fn page_size_bits(&self) -> usize {
+        1
+    }
+
+    fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size;
+    }
+}
+
+pub struct P18 {
+    pub threads: usize,
+    pub page_size: usize,
+    pub executor: Executor,
+    pub schedule: Schedule,
+}
+
+impl Default for P18 {
+    fn default() -> P18 {
+        P18 {
+            threads: 16,
+            page_size: 4096,
+            executor: Executor::default(),
+            schedule: Schedule::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P18 {
+    fn name(&self) -> &'static str {
+        "p18"
+    }
+
+    fn description(&self) -> &'static str {
+        "like p17, but only the odd numbers"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        self.count_with_load(max).0
+    }
+
+    fn count_with_load(&self, max: u64) -> (u64, Option<Load>) {
+        let (count, load) = count_primes_p18(
+            max as usize,
+            self.threads,
+            self.page_size,
+            self.executor,
+            self.schedule,
+        );
+        (count, Some(load))
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.page_size)
+    }
+
+    fn page_size_bits(&self) -> usize {
+        1
+    }
+
+    fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size;
+    }
+}
+
+// the wheel sieve, `p19` with the mod 30 wheel and `p19-210` with the mod 210 wheel
+pub struct P19 {
+    pub threads: usize,
+    pub page_size: usize,
+    pub wheel: Wheel,
+    pub executor: Executor,
+    pub schedule: Schedule,
+}
+
+impl Default for P19 {
+    fn default() -> P19 {
+        P19 {
+            threads: 16,
+            page_size: 4096,
+            wheel: Wheel::mod30(),
+            executor: Executor::default(),
+            schedule: Schedule::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P19 {
+    fn name(&self) -> &'static str {
+        match self.wheel.modulus() {
+            30 => "p19",
+            _ => "p19-210",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self.wheel.modulus() {
+            30 => "Sieve, multithreaded, only the numbers coprime to 30",
+            _ => "like p19, but only the numbers coprime to 210",
+        }
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        self.count_with_load(max).0
+    }
+
+    fn count_with_load(&self, max: u64) -> (u64, Option<Load>) {
+        let (count, load) = count_primes_p19(
+            max as usize,
+            &self.wheel,
+            self.threads,
+            self.page_size,
+            self.executor,
+            self.schedule,
+        );
+        (count, Some(load))
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.page_size)
+    }
+
+    fn page_size_bits(&self) -> usize {
+        1
+    }
+
+    fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size;
+    }
+}
+
+// the page size is the buffer size of each thread
+pub struct P20 {
+    pub threads: usize,
+    pub page_size: usize,
+    pub executor: Executor,
+    pub schedule: Schedule,
+}
+
+impl Default for P20 {
+    fn default() -> P20 {
+        P20 {
+            threads: hw::cores(),
+            page_size: CacheSizes::detect().segment_size(),
+            executor: Executor::default(),
+            schedule: Schedule::default(),
+        }
+    }
+}
+
+impl PrimeCounter for P20 {
+    fn name(&self) -> &'static str {
+        "p20"
+    }
+
+    fn description(&self) -> &'static str {
+        "Segmented sieve, odd numbers, one cache-sized buffer per thread"
+    }
+
+    fn count(&self, max: u64) -> u64 {
+        self.count_with_load(max).0
+    }
+
+    fn count_with_load(&self, max: u64) -> (u64, Option<Load>) {
+        let sieve = SegmentedSieve {
+            executor: self.executor,
+            schedule: self.schedule,
+            ..SegmentedSieve::new(self.threads, self.page_size)
+        };
+        let (count, load) = sieve.count(max as usize);
+        (count, Some(load))
+    }
+
+    fn threads(&self) -> Option<usize> {
+        Some(self.threads)
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        Some(self.page_size)
+    }
+
+    fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size;
+    }
+}
@@ -0,0 +1,87 @@
+//! Wheels for sieving only the numbers coprime to the first few primes.
+//!
+//! A wheel of the primes 2, 3, 5 has the modulus 30 and 8 residues coprime to it (1, 7, 11, 13,
+//! 17, 19, 23, 29), so a sieve needs 8 bits per 30 numbers instead of 30. Bit `i` stands for the
+//! number `value(i)`, turn `i / 8` of the wheel at residue `i % 8`.
+
+/// The residues coprime to the product of a few small primes.
+#[derive(Clone, Debug)]
+pub struct Wheel {
+    primes: Vec<usize>,
+    modulus: usize,
+    residues: Vec<usize>,
+    // positions[r] is the index of the residue r, None if r is not coprime to the modulus
+    positions: Vec<Option<usize>>,
+}
+
+impl Wheel {
+    /// Creates the wheel of the product of `primes`.
+    pub fn new(primes: &[usize]) -> Wheel {
+        let modulus: usize = primes.iter().product();
+        let mut residues = vec![];
+        let mut positions = vec![None; modulus];
+        for (r, position) in positions.iter_mut().enumerate() {
+            if primes.iter().all(|&p| !r.is_multiple_of(p)) {
+                *position = Some(residues.len());
+                residues.push(r);
+            }
+        }
+        Wheel {
+            primes: primes.to_vec(),
+            modulus,
+            residues,
+            positions,
+        }
+    }
+
+    /// The wheel of 2, 3, 5, 8 residues per 30 numbers.
+    pub fn mod30() -> Wheel {
+        Wheel::new(&[2, 3, 5])
+    }
+
+    /// The wheel of 2, 3, 5, 7, 48 residues per 210 numbers.
+    pub fn mod210() -> Wheel {
+        Wheel::new(&[2, 3, 5, 7])
+    }
+
+    /// Returns the wheel with this modulus, 30 or 210.
+    pub fn with_modulus(modulus: usize) -> Option<Wheel> {
+        match modulus {
+            30 => Some(Wheel::mod30()),
+            210 => Some(Wheel::mod210()),
+            _ => None,
+        }
+    }
+
+    /// Returns the primes the wheel is built of.
+    pub fn primes(&self) -> &[usize] {
+        &self.primes
+    }
+
+    pub fn modulus(&self) -> usize {
+        self.modulus
+    }
+
+    /// Returns the residues coprime to the modulus in ascending order, starting with 1.
+    pub fn residues(&self) -> &[usize] {
+        &self.residues
+    }
+
+    /// Returns the number bit `idx` stands for.
+    pub fn value(&self, idx: usize) -> usize {
+        let spokes = self.residues.len();
+        idx / spokes * self.modulus + self.residues[idx % spokes]
+    }
+
+    /// Returns the bit of `n`, `None` if `n` shares a factor with the modulus.
+    pub fn index(&self, n: usize) -> Option<usize> {
+        let position = self.positions[n % self.modulus]?;
+        Some(n / self.modulus * self.residues.len() + position)
+    }
+
+    /// Returns the number of bits for the numbers below `max`.
+    pub fn count_below(&self, max: usize) -> usize {
+        let rest = self.residues.partition_point(|&r| r < max % self.modulus);
+        max / self.modulus * self.residues.len() + rest
+    }
+}
@@ -0,0 +1,99 @@
+// Checks the big integer arithmetic against u128 and against its own identities for numbers of
+// many limbs.
+
+use parallel_primes::bigint::BigUint;
+
+// a deterministic stream of values, with many all-zero and all-one limbs to hit the carries
+fn values(count: usize) -> Vec<u128> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut values = vec![0, 1, u64::MAX as u128, 1 << 64, u128::MAX, u128::MAX - 1];
+    while values.len() < count {
+        let bits = next() % 129;
+        let v = (next() as u128) << 64 | next() as u128;
+        values.push(if bits == 128 {
+            v
+        } else {
+            v & ((1 << bits) - 1)
+        });
+    }
+    values
+}
+
+// a number of `limbs` limbs built from the values
+fn big(seed: &[u128], limbs: usize) -> BigUint {
+    let mut n = BigUint::zero();
+    for i in 0..limbs.div_ceil(2) {
+        n = &(&n << 128) + &BigUint::from(seed[i % seed.len()]);
+    }
+    n
+}
+
+#[test]
+fn matches_u128() {
+    let values = values(60);
+    for &a in &values {
+        for &b in &values {
+            let (x, y) = (BigUint::from(a), BigUint::from(b));
+            assert_eq!(x.cmp(&y), a.cmp(&b));
+            if let Some(sum) = a.checked_add(b) {
+                assert_eq!(&x + &y, BigUint::from(sum), "{} + {}", a, b);
+            }
+            if a >= b {
+                assert_eq!(&x - &y, BigUint::from(a - b), "{} - {}", a, b);
+            }
+            if let Some(product) = a.checked_mul(b) {
+                assert_eq!(&x * &y, BigUint::from(product), "{} * {}", a, b);
+            }
+            if let (Some(quotient), Some(rest)) = (a.checked_div(b), a.checked_rem(b)) {
+                assert_eq!(&x / &y, BigUint::from(quotient), "{} / {}", a, b);
+                assert_eq!(&x % &y, BigUint::from(rest), "{} % {}", a, b);
+            }
+        }
+        let x = BigUint::from(a);
+        assert_eq!(x.to_string(), a.to_string());
+        assert_eq!(a.to_string().parse::<BigUint>(), Ok(x.clone()));
+        assert_eq!(x.bits(), 128 - a.leading_zeros() as u64);
+        assert_eq!(&(&x << 3) >> 3, x);
+        assert_eq!(&x >> 70, BigUint::from(a >> 70));
+        assert_eq!(x.sqrt(), BigUint::from(a.isqrt()));
+    }
+}
+
+#[test]
+fn division_identity() {
+    let values = values(40);
+    let one = BigUint::one();
+    for limbs in [2, 3, 5, 8, 17] {
+        for shift in 0..5 {
+            let a = big(&values[shift..], limbs * 2);
+            let d = &big(&values[shift + 7..], limbs) + &one;
+            let (q, r) = a.div_rem(&d);
+            assert!(r < d);
+            assert_eq!(&(&q * &d) + &r, a);
+        }
+    }
+}
+
+#[test]
+fn parse_and_format() {
+    let digits = "1".repeat(300);
+    let n: BigUint = digits.parse().unwrap();
+    assert_eq!(n.to_string(), digits);
+    assert_eq!(format!("{:>5}", BigUint::from(42u64)), "   42");
+    assert_eq!(BigUint::zero().to_string(), "0");
+    assert!("12a".parse::<BigUint>().is_err());
+    assert!("".parse::<BigUint>().is_err());
+    // 10^40 = 2^40 * 5^40
+    let ten_40: BigUint = format!("1{}", "0".repeat(40)).parse().unwrap();
+    assert_eq!(ten_40.trailing_zeros(), 40);
+    assert_eq!(
+        BigUint::from(10u64).pow_mod(&BigUint::from(40u64), &(&ten_40 + &BigUint::one())),
+        ten_40
+    );
+}
@@ -0,0 +1,31 @@
+// Checks the parsing of the tuned config file.
+
+use parallel_primes::config::{Config, Tunables};
+
+#[test]
+fn parse_sections() {
+    let config =
+        Config::parse("# tuned\n[p17]\nthreads = 16\npage_size = 4096\nmax = 3000000000\n")
+            .unwrap();
+    let tuned = config.get("p17").unwrap();
+    assert_eq!(
+        tuned,
+        Tunables {
+            threads: 16,
+            page_size: 4096,
+            max: 3_000_000_000
+        }
+    );
+    assert!(config.get("p16").is_none());
+    assert!(tuned.applies_to(1_000_000_000));
+    assert!(tuned.applies_to(30_000_000_000));
+    assert!(!tuned.applies_to(1_000_000));
+}
+
+#[test]
+fn rejects_zero_threads_and_page_size() {
+    let err = Config::parse("[p17]\nthreads = 0\npage_size = 4096\nmax = 1000\n").unwrap_err();
+    assert!(err.to_string().contains("line 2"), "{}", err);
+    assert!(Config::parse("[p17]\nthreads = 4\npage_size = 0\nmax = 1000\n").is_err());
+    assert!(Config::parse("[p17]\nthreads = 4\npage_size = 64\n").is_err());
+}
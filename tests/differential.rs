@@ -0,0 +1,296 @@
+// Runs every algorithm over many limits and checks they all find the same primes.
+
+use parallel_primes::counter;
+use parallel_primes::pool::Executor;
+use parallel_primes::schedule::Schedule;
+use parallel_primes::segmented::SegmentedSieve;
+use parallel_primes::sieve::{
+    count_primes_p17, count_primes_p17_words, count_primes_p18, count_primes_p19, odd_primes_p18,
+    odd_sieve, packed_sieve, recursive_primes_p12, recursive_primes_p12a, recursive_primes_p16,
+    recursive_primes_p17, sieve, wheel_primes_p19,
+};
+use parallel_primes::trial_division::{is_prime1, prime_slice, Primes3};
+use parallel_primes::wheel::Wheel;
+use parallel_primes::PackedBits;
+
+const PAGE_SIZES: [usize; 6] = [1, 2, 64, 100, 256, 4096];
+const THREAD_COUNTS: [usize; 6] = [1, 2, 3, 4, 8, 16];
+
+// the edge cases, powers of two and page sizes plus/minus one, and a few larger limits
+fn limits() -> Vec<u64> {
+    let mut limits = vec![
+        0, 1, 2, 3, 4, 5, 10, 64, 65, 100, 101, 150, 1000, 10_007, 65_536, 100_000,
+    ];
+    for page_size in PAGE_SIZES {
+        let page_size = page_size as u64;
+        limits.extend([page_size - 1, page_size, page_size + 1]);
+        limits.extend([2 * page_size - 1, 2 * page_size + 1]);
+    }
+    limits.sort_unstable();
+    limits.dedup();
+    limits
+}
+
+fn trial_division(max: u64) -> Vec<u64> {
+    (0..max).filter(|&n| is_prime1(n) == 1).collect()
+}
+
+fn from_bools(primes: &[bool], max: u64) -> Vec<u64> {
+    assert!(primes.len() as u64 >= max, "only {} entries", primes.len());
+    (0..max).filter(|&n| primes[n as usize]).collect()
+}
+
+fn from_packed(primes: &PackedBits, max: u64) -> Vec<u64> {
+    assert!(primes.len() as u64 >= max, "only {} bits", primes.len());
+    (0..max).filter(|&n| primes.is_set(n as usize)).collect()
+}
+
+// bit i of an odd sieve is 2 * i + 1, 2 is added
+fn from_odd(primes: &PackedBits, max: u64) -> Vec<u64> {
+    assert!(primes.len() as u64 >= max / 2, "only {} bits", primes.len());
+    let odd = (0..max / 2).filter(|&i| primes.is_set(i as usize));
+    (2..3.min(max)).chain(odd.map(|i| 2 * i + 1)).collect()
+}
+
+// bit i of a wheel sieve is wheel.value(i), the wheel's primes are added
+fn from_wheel(primes: &PackedBits, wheel: &Wheel, max: u64) -> Vec<u64> {
+    assert_eq!(primes.len(), wheel.count_below(max as usize));
+    let mut result: Vec<u64> = wheel.primes().iter().map(|&p| p as u64).collect();
+    result.retain(|&p| p < max);
+    result.extend(primes.iter_ones().map(|i| wheel.value(i) as u64));
+    result
+}
+
+fn memoized(max: u64) -> Vec<u64> {
+    let mut primes: Vec<u64> = [2, 3].into_iter().filter(|&p| p < max).collect();
+    let mut p = Primes3::new();
+    let mut i = 5;
+    while i < max {
+        if p.to_prime_mut(i) == 1 {
+            primes.push(i);
+        }
+        i += 2;
+    }
+    primes
+}
+
+fn sliced(max: u64) -> Vec<u64> {
+    let small_primes = [2, 3, 5, 7, 11, 13];
+    let mut primes: Vec<u64> = small_primes.into_iter().filter(|&p| p < max).collect();
+    let mut last = 15;
+    while last < max {
+        let end = (last * last).min(max);
+        let mut v = prime_slice(&primes, last, end);
+        primes.append(&mut v);
+        last = end + 1 - end % 2;
+    }
+    primes
+}
+
+#[test]
+fn single_threaded() {
+    for max in limits() {
+        let expected = trial_division(max);
+        assert_eq!(memoized(max), expected, "Primes3, max={}", max);
+        assert_eq!(sliced(max), expected, "prime_slice, max={}", max);
+        if max >= 1 {
+            assert_eq!(
+                from_bools(&sieve(max - 1), max),
+                expected,
+                "sieve, max={}",
+                max
+            );
+            assert_eq!(
+                from_packed(&packed_sieve(max - 1), max),
+                expected,
+                "packed_sieve, max={}",
+                max
+            );
+            assert_eq!(
+                from_odd(&odd_sieve(max - 1), max),
+                expected,
+                "odd_sieve, max={}",
+                max
+            );
+        }
+    }
+}
+
+#[test]
+fn multithreaded() {
+    for max in limits() {
+        let expected = trial_division(max);
+        for threads in THREAD_COUNTS {
+            let primes = recursive_primes_p12(max as usize, threads, Executor::Pool);
+            assert_eq!(
+                from_bools(&primes, max),
+                expected,
+                "recursive_primes_p12, max={}, threads={}",
+                max,
+                threads
+            );
+            for page_size in PAGE_SIZES {
+                let primes =
+                    recursive_primes_p12a(max as usize, threads, page_size, Executor::Pool);
+                assert_eq!(
+                    from_bools(&primes, max),
+                    expected,
+                    "recursive_primes_p12a, max={}, threads={}, page_size={}",
+                    max,
+                    threads,
+                    page_size
+                );
+                let primes = recursive_primes_p16(max as usize, threads, page_size, Executor::Pool);
+                assert_eq!(primes.len() as u64, max);
+                assert_eq!(
+                    from_bools(&primes, max),
+                    expected,
+                    "recursive_primes_p16, max={}, threads={}, page_size={}",
+                    max,
+                    threads,
+                    page_size
+                );
+                let primes = recursive_primes_p17(max as usize, threads, page_size, Executor::Pool);
+                assert_eq!(primes.len() as u64, max);
+                assert_eq!(
+                    from_packed(&primes, max),
+                    expected,
+                    "recursive_primes_p17, max={}, threads={}, page_size={}",
+                    max,
+                    threads,
+                    page_size
+                );
+                let primes = odd_primes_p18(max as usize, threads, page_size, Executor::Pool);
+                assert_eq!(primes.len() as u64, max / 2);
+                assert_eq!(
+                    from_odd(&primes, max),
+                    expected,
+                    "odd_primes_p18, max={}, threads={}, page_size={}",
+                    max,
+                    threads,
+                    page_size
+                );
+                let count = expected.len() as u64;
+                let (pool, schedule) = (Executor::Pool, Schedule::default());
+                let counts = [
+                    count_primes_p17(max as usize, threads, page_size, pool, schedule),
+                    count_primes_p17_words::<u8>(max as usize, threads, page_size, pool, schedule),
+                    count_primes_p17_words::<u32>(max as usize, threads, page_size, pool, schedule),
+                    count_primes_p18(max as usize, threads, page_size, pool, schedule),
+                    count_primes_p19(
+                        max as usize,
+                        &Wheel::mod30(),
+                        threads,
+                        page_size,
+                        pool,
+                        schedule,
+                    ),
+                    count_primes_p19(
+                        max as usize,
+                        &Wheel::mod210(),
+                        threads,
+                        page_size,
+                        pool,
+                        schedule,
+                    ),
+                ]
+                .map(|(count, _)| count);
+                assert_eq!(
+                    counts, [count; 6],
+                    "count_primes_p17/18/19, max={}, threads={}, page_size={}",
+                    max, threads, page_size
+                );
+                for wheel in [Wheel::mod30(), Wheel::mod210()] {
+                    let primes =
+                        wheel_primes_p19(max as usize, &wheel, threads, page_size, Executor::Pool);
+                    assert_eq!(
+                        from_wheel(&primes, &wheel, max),
+                        expected,
+                        "wheel_primes_p19, mod {}, max={}, threads={}, page_size={}",
+                        wheel.modulus(),
+                        max,
+                        threads,
+                        page_size
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn segmented() {
+    for max in limits() {
+        let expected = trial_division(max);
+        for threads in THREAD_COUNTS {
+            for segment_size in [1, 8, 13, 512] {
+                let sieve = SegmentedSieve::new(threads, segment_size);
+                let primes = sieve.primes(max as usize);
+                assert_eq!(primes.len() as u64, max / 2);
+                assert_eq!(
+                    from_odd(&primes, max),
+                    expected,
+                    "SegmentedSieve, max={}, threads={}, segment_size={}",
+                    max,
+                    threads,
+                    segment_size
+                );
+                assert_eq!(sieve.count(max as usize).0, expected.len() as u64);
+            }
+        }
+    }
+}
+
+#[test]
+fn odd_only_matches_p17() {
+    let max = 2_000_003;
+    let all = recursive_primes_p17(max, 4, 4096, Executor::Spawn);
+    let odd = odd_primes_p18(max, 4, 4096, Executor::Pool);
+    for (i, n) in (1..max).step_by(2).enumerate() {
+        assert_eq!(odd.is_set(i), all.is_set(n), "n={}", n);
+    }
+}
+
+#[test]
+fn wheels() {
+    for wheel in [Wheel::mod30(), Wheel::mod210()] {
+        let modulus = wheel.modulus();
+        let coprime: Vec<usize> = (0..3 * modulus + 5)
+            .filter(|&n| wheel.primes().iter().all(|&p| n % p != 0))
+            .collect();
+        for (idx, &n) in coprime.iter().enumerate() {
+            assert_eq!(wheel.value(idx), n, "mod {}", modulus);
+            assert_eq!(wheel.index(n), Some(idx), "mod {}", modulus);
+            assert_eq!(wheel.count_below(n), idx, "mod {}", modulus);
+            assert_eq!(wheel.index(n + 1), None, "mod {}", modulus);
+        }
+    }
+    assert_eq!(Wheel::mod30().residues(), [1, 7, 11, 13, 17, 19, 23, 29]);
+    assert_eq!(Wheel::mod210().residues().len(), 48);
+}
+
+#[test]
+fn counters() {
+    for max in limits() {
+        let expected = trial_division(max).len() as u64;
+        for mut counter in counter::all() {
+            assert_eq!(
+                counter.count(max),
+                expected,
+                "{}, max={}",
+                counter.name(),
+                max
+            );
+            if counter.threads().is_some() {
+                counter.set_threads(3);
+                assert_eq!(
+                    counter.count(max),
+                    expected,
+                    "{}, max={}",
+                    counter.name(),
+                    max
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// Checks the factorization against trial division and on numbers with large prime factors.
+
+use parallel_primes::factor::factor;
+use parallel_primes::primality::is_prime_u64;
+
+fn trial_division(mut n: u64) -> Vec<(u64, u32)> {
+    let mut factors = vec![];
+    let mut p = 2;
+    while p * p <= n {
+        let mut exponent = 0;
+        while n.is_multiple_of(p) {
+            n /= p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+// the factors are primes in ascending order and multiply to n
+fn check(n: u64) {
+    let factors = factor(n);
+    let mut product = 1u128;
+    for (i, &(p, exponent)) in factors.iter().enumerate() {
+        assert!(is_prime_u64(p), "{} in the factors of {}", p, n);
+        assert!(i == 0 || factors[i - 1].0 < p, "{:?}", factors);
+        product *= (p as u128).pow(exponent);
+    }
+    assert_eq!(product, n as u128, "{:?}", factors);
+}
+
+#[test]
+fn matches_trial_division() {
+    assert_eq!(factor(0), []);
+    assert_eq!(factor(1), []);
+    for n in 2..50_000 {
+        assert_eq!(factor(n), trial_division(n), "{}", n);
+    }
+    for n in (1 << 40)..(1 << 40) + 200 {
+        assert_eq!(factor(n), trial_division(n), "{}", n);
+    }
+}
+
+#[test]
+fn large_factors() {
+    let p32 = 4_294_967_291;
+    let q32 = 4_294_967_279;
+    assert_eq!(factor(p32 * q32), [(q32, 1), (p32, 1)]);
+    assert_eq!(factor(p32 * p32), [(p32, 2)]);
+    assert_eq!(
+        factor(u64::MAX),
+        [
+            (3, 1),
+            (5, 1),
+            (17, 1),
+            (257, 1),
+            (641, 1),
+            (65537, 1),
+            (6700417, 1)
+        ]
+    );
+    // 2^67 - 1 is too large, but its factor 761838257287 times a 24 bit prime fits
+    assert_eq!(
+        factor(761_838_257_287 * 16_777_213),
+        [(16_777_213, 1), (761_838_257_287, 1)]
+    );
+    assert_eq!(
+        factor(3_825_123_056_546_413_051),
+        [(149_491, 1), (747_451, 1), (34_233_211, 1)]
+    );
+    assert_eq!(factor(4099 * 4099 * 4099), [(4099, 3)]);
+    assert_eq!(factor(1 << 63), [(2, 63)]);
+    for n in (u64::MAX - 1000..=u64::MAX).chain((1 << 62)..(1 << 62) + 1000) {
+        check(n);
+    }
+}
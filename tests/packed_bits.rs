@@ -0,0 +1,380 @@
+// Checks PackedBits against a Vec<bool> model, especially at offsets that are not multiples of 64.
+
+use parallel_primes::packed_bits::Word;
+use parallel_primes::reference::count_below;
+use parallel_primes::sieve::packed_sieve;
+use parallel_primes::{PackedBits, RankSelect};
+
+// a bit pattern that differs between neighbouring bits and words
+fn pattern(len: usize, seed: usize) -> Vec<bool> {
+    (0..len).map(|i| (i * 7 + seed).is_multiple_of(3)).collect()
+}
+
+fn packed(bits: &[bool]) -> PackedBits {
+    let mut packed = PackedBits::new_set(bits.len(), true);
+    for (idx, &bit) in bits.iter().enumerate() {
+        if !bit {
+            packed.clear(idx);
+        }
+    }
+    packed
+}
+
+fn packed_words<W: Word>(bits: &[bool]) -> PackedBits<W> {
+    let mut packed = PackedBits::new_set(bits.len(), false);
+    for (idx, &bit) in bits.iter().enumerate() {
+        packed.assign(idx, bit);
+    }
+    packed
+}
+
+fn unpacked<W: Word>(packed: &PackedBits<W>) -> Vec<bool> {
+    (0..packed.len()).map(|idx| packed.is_set(idx)).collect()
+}
+
+const LENGTHS: [usize; 12] = [0, 1, 2, 63, 64, 65, 100, 127, 128, 129, 191, 300];
+
+#[test]
+fn new_set_clears_unused_bits() {
+    for len in LENGTHS {
+        let bits: PackedBits = PackedBits::new_set(len, true);
+        assert_eq!(bits.count_ones(len), len);
+        assert_eq!(unpacked(&bits), vec![true; len]);
+    }
+}
+
+#[test]
+fn append_at_any_offset() {
+    for a in LENGTHS {
+        for b in LENGTHS {
+            let mut expected = pattern(a, 1);
+            expected.extend(pattern(b, 2));
+
+            let mut left = packed(&pattern(a, 1));
+            let mut right = packed(&pattern(b, 2));
+            left.append(&mut right);
+            assert_eq!(left.len(), a + b, "a={}, b={}", a, b);
+            assert_eq!(unpacked(&left), expected, "a={}, b={}", a, b);
+            let ones = expected.iter().filter(|&&bit| bit).count();
+            assert_eq!(left.count_ones(a + b), ones, "a={}, b={}", a, b);
+            assert!(right.is_empty());
+        }
+    }
+}
+
+#[test]
+fn append_all_ones_keeps_tail_clear() {
+    let mut bits: PackedBits = PackedBits::new_set(0, true);
+    for len in LENGTHS {
+        bits.append(&mut PackedBits::new_set(len, true));
+    }
+    let total: usize = LENGTHS.iter().sum();
+    assert_eq!(bits.len(), total);
+    assert_eq!(bits.count_ones(total), total);
+    bits.append(&mut PackedBits::new_set(10, false));
+    assert_eq!(bits.count_ones(total + 10), total);
+}
+
+#[test]
+fn truncate() {
+    for len in LENGTHS {
+        for at in 0..=len {
+            let bits = pattern(len, 3);
+            let mut packed = packed(&bits);
+            packed.truncate(at);
+            assert_eq!(unpacked(&packed), bits[..at], "len={}, at={}", len, at);
+            // the bits past the new length must not come back when growing again
+            packed.append(&mut PackedBits::new_set(len - at, false));
+            let ones = bits[..at].iter().filter(|&&bit| bit).count();
+            assert_eq!(packed.count_ones(len), ones, "len={}, at={}", len, at);
+        }
+    }
+}
+
+#[test]
+fn split_off() {
+    for len in LENGTHS {
+        for at in 0..=len {
+            let bits = pattern(len, 4);
+            let mut packed = packed(&bits);
+            let tail = packed.split_off(at);
+            assert_eq!(unpacked(&packed), bits[..at], "len={}, at={}", len, at);
+            assert_eq!(unpacked(&tail), bits[at..], "len={}, at={}", len, at);
+            let ones = bits[at..].iter().filter(|&&bit| bit).count();
+            assert_eq!(tail.count_ones(len - at), ones, "len={}, at={}", len, at);
+        }
+    }
+}
+
+#[test]
+fn set_toggle_assign() {
+    for len in LENGTHS {
+        let mut expected = vec![false; len];
+        let mut packed: PackedBits = PackedBits::new_set(len, false);
+        for idx in (0..len).step_by(3) {
+            packed.set(idx);
+            expected[idx] = true;
+        }
+        for idx in (0..len).step_by(5) {
+            packed.toggle(idx);
+            expected[idx] = !expected[idx];
+        }
+        for idx in (0..len).step_by(7) {
+            packed.assign(idx, idx % 2 == 0);
+            expected[idx] = idx % 2 == 0;
+        }
+        assert_eq!(unpacked(&packed), expected, "len={}", len);
+    }
+}
+
+#[test]
+fn set_and_clear_ranges() {
+    for len in LENGTHS {
+        for start in (0..=len).step_by(7) {
+            for end in (start..=len).step_by(11) {
+                let mut expected = pattern(len, 5);
+                let mut packed = packed(&expected);
+                packed.set_range(start..end);
+                expected[start..end].fill(true);
+                assert_eq!(unpacked(&packed), expected, "set {}..{}", start, end);
+                let clear = start / 2..(end + len) / 2;
+                packed.clear_range(clear.clone());
+                expected[clear.clone()].fill(false);
+                assert_eq!(unpacked(&packed), expected, "clear {:?}", clear);
+            }
+        }
+    }
+}
+
+#[test]
+fn words() {
+    let words = vec![0x8000_0000_0000_0001, u64::MAX, 0xff];
+    let packed = PackedBits::from_words(words.clone());
+    assert_eq!(packed.len(), 192);
+    assert_eq!(packed.as_words(), &words[..]);
+    assert!(packed.is_set(0) && !packed.is_set(1) && packed.is_set(63));
+    assert_eq!(packed.get_word(2), 0xff);
+
+    let packed = PackedBits::from_words_len(words, 132);
+    assert_eq!(packed.len(), 132);
+    assert_eq!(packed.get_word(2), 0xf);
+    assert_eq!(packed.count_ones(132), 2 + 64 + 4);
+}
+
+#[test]
+fn iterators() {
+    for len in LENGTHS {
+        let bits = pattern(len, 6);
+        let packed = packed(&bits);
+        let ones: Vec<usize> = (0..len).filter(|&idx| bits[idx]).collect();
+        let zeros: Vec<usize> = (0..len).filter(|&idx| !bits[idx]).collect();
+
+        assert_eq!(packed.iter_ones().len(), ones.len());
+        assert_eq!(packed.iter_ones().collect::<Vec<_>>(), ones);
+        assert_eq!(packed.iter_zeros().collect::<Vec<_>>(), zeros);
+        let mut reversed: Vec<usize> = packed.iter_zeros().rev().collect();
+        reversed.reverse();
+        assert_eq!(reversed, zeros, "len={}", len);
+
+        for start in (0..=len).step_by(13) {
+            for end in (start..=len).step_by(17) {
+                let in_range = |idx: &&usize| (start..end).contains(*idx);
+                let ones_in: Vec<usize> = ones.iter().filter(in_range).copied().collect();
+                let zeros_in: Vec<usize> = zeros.iter().filter(in_range).copied().collect();
+                assert_eq!(packed.iter_ones_in(start..end).collect::<Vec<_>>(), ones_in);
+                assert_eq!(packed.iter_zeros_in(start..end).len(), zeros_in.len());
+                let mut reversed: Vec<usize> = packed.iter_ones_in(start..end).rev().collect();
+                reversed.reverse();
+                assert_eq!(reversed, ones_in, "{}..{}", start, end);
+
+                // alternating from both ends meets in the middle
+                let mut iter = packed.iter_zeros_in(start..end);
+                let mut front = vec![];
+                let mut back = vec![];
+                while let Some(idx) = iter.next() {
+                    front.push(idx);
+                    if let Some(idx) = iter.next_back() {
+                        back.push(idx);
+                    }
+                }
+                back.reverse();
+                front.extend(back);
+                assert_eq!(front, zeros_in, "{}..{}", start, end);
+            }
+        }
+    }
+}
+
+#[test]
+fn iterate_all_ones_across_words() {
+    let packed: PackedBits = PackedBits::new_set(200, true);
+    assert!(packed.iter_ones().eq(0..200));
+    assert!(packed.iter_ones().rev().eq((0..200).rev()));
+    assert_eq!(packed.iter_zeros().next(), None);
+}
+
+#[test]
+fn bitwise_operations() {
+    for len in LENGTHS {
+        let (a, b) = (pattern(len, 1), pattern(len, 2));
+        let expected = |f: fn(bool, bool) -> bool| -> Vec<bool> {
+            a.iter().zip(&b).map(|(&x, &y)| f(x, y)).collect()
+        };
+        let (x, y) = (packed(&a), packed(&b));
+        assert_eq!(unpacked(&(&x & &y)), expected(|x, y| x & y), "len={}", len);
+        assert_eq!(unpacked(&(&x | &y)), expected(|x, y| x | y), "len={}", len);
+        assert_eq!(unpacked(&(&x ^ &y)), expected(|x, y| x ^ y), "len={}", len);
+        assert_eq!(unpacked(&x.and(&y)), expected(|x, y| x & y), "len={}", len);
+        let not = !&x;
+        assert_eq!(unpacked(&not), expected(|x, _| !x), "len={}", len);
+        assert_eq!(not.count_ones(len), len - x.count_ones(len), "len={}", len);
+        assert_eq!(unpacked(&!not), a, "len={}", len);
+
+        let mut z = x.clone();
+        z |= &y;
+        z ^= &x;
+        z &= &y;
+        assert_eq!(unpacked(&z), expected(|x, y| (x | y) ^ x), "len={}", len);
+    }
+}
+
+#[test]
+fn shifted_combine() {
+    for len in LENGTHS {
+        for other_len in [0, len / 2, len, len + 70] {
+            for shift in [-130, -65, -64, -3, 0, 1, 2, 63, 64, 65, 200] {
+                let a = pattern(len, 1);
+                let b = pattern(other_len, 2);
+                let other = |i: usize| {
+                    let j = i as isize + shift;
+                    j >= 0 && (j as usize) < other_len && b[j as usize]
+                };
+                let mut bits = packed(&a);
+                bits.xor_shifted(&packed(&b), shift);
+                let expected: Vec<bool> = (0..len).map(|i| a[i] ^ other(i)).collect();
+                assert_eq!(
+                    unpacked(&bits),
+                    expected,
+                    "{}, {}, {}",
+                    len,
+                    other_len,
+                    shift
+                );
+                assert_eq!(
+                    bits.count_ones(len),
+                    expected.iter().filter(|&&x| x).count()
+                );
+            }
+        }
+        let a = pattern(len, 1);
+        let shifted: Vec<bool> = (0..len).map(|i| i >= 3 && a[i - 3]).collect();
+        assert_eq!(unpacked(&packed(&a).shifted(-3)), shifted, "len={}", len);
+    }
+}
+
+#[test]
+fn twin_primes() {
+    let primes = packed_sieve(100_000);
+    let twins = &primes & &primes.shifted(2);
+    let mut in_place = primes.clone();
+    in_place.and_shifted(&primes, 2);
+    assert_eq!(twins.as_words(), in_place.as_words());
+    assert_eq!(
+        twins.iter_ones().take(5).collect::<Vec<_>>(),
+        [3, 5, 11, 17, 29]
+    );
+    // pairs with both primes below 100000
+    assert_eq!(twins.count_ones(100_000 - 2), 1224);
+}
+
+#[test]
+fn rank_select() {
+    // several blocks and select samples, dense, sparse and empty
+    let mut lengths = LENGTHS.to_vec();
+    lengths.extend([511, 512, 513, 4096, 20_000]);
+    for len in lengths {
+        for bits in [
+            pattern(len, 1),
+            (0..len).map(|i| i % 97 == 5).collect(),
+            vec![true; len],
+            vec![false; len],
+        ] {
+            let index = RankSelect::new(packed(&bits));
+            let ones: Vec<usize> = (0..len).filter(|&i| bits[i]).collect();
+            assert_eq!(index.count_ones(), ones.len(), "len={}", len);
+            let mut rank = 0;
+            for (n, &bit) in bits.iter().enumerate() {
+                assert_eq!(index.rank(n), rank, "len={}, n={}", len, n);
+                rank += bit as usize;
+            }
+            assert_eq!(index.rank(len), rank, "len={}", len);
+            for (k, &idx) in ones.iter().enumerate() {
+                assert_eq!(index.select(k), Some(idx), "len={}, k={}", len, k);
+            }
+            assert_eq!(index.select(ones.len()), None, "len={}", len);
+        }
+    }
+}
+
+#[test]
+fn prime_pi_and_nth_prime() {
+    let primes = RankSelect::new(packed_sieve(1_000_000));
+    for n in [0, 1, 2, 3, 1000, 7919, 65_536, 999_983, 1_000_000] {
+        assert_eq!(
+            primes.rank(n + 1) as u64,
+            count_below(n as u64 + 1),
+            "n={}",
+            n
+        );
+    }
+    assert_eq!(primes.select(0), Some(2));
+    assert_eq!(primes.select(999), Some(7919));
+    assert_eq!(primes.select(78_497), Some(999_983));
+    assert_eq!(primes.select(78_498), None);
+}
+
+// the same operations on every word size, the word boundaries fall on different bits
+fn check_word_size<W: Word>() {
+    for a in LENGTHS {
+        let bits = pattern(a, 1);
+        let ones: Vec<usize> = (0..a).filter(|&i| bits[i]).collect();
+        let packed = packed_words::<W>(&bits);
+        assert_eq!(packed.as_words().len(), a.div_ceil(W::BITS));
+        assert_eq!(packed.count_ones(a), ones.len(), "{:?}, len={}", W::ONE, a);
+        assert_eq!(packed.iter_ones().collect::<Vec<_>>(), ones);
+        assert!(packed
+            .iter_zeros()
+            .rev()
+            .eq((0..a).rev().filter(|&i| !bits[i])));
+        let mut not = !&packed;
+        not.xor_shifted(&packed, -1);
+        let expected: Vec<bool> = (0..a).map(|i| !bits[i] ^ (i > 0 && bits[i - 1])).collect();
+        assert_eq!(unpacked(&not), expected, "{:?}, len={}", W::ONE, a);
+        let index = RankSelect::new(packed.clone());
+        for (k, &idx) in ones.iter().enumerate() {
+            assert_eq!(index.rank(idx), k);
+            assert_eq!(index.select(k), Some(idx));
+        }
+
+        for b in LENGTHS {
+            let mut left = packed.clone();
+            left.append(&mut packed_words(&pattern(b, 2)));
+            let mut expected = bits.clone();
+            expected.extend(pattern(b, 2));
+            assert_eq!(unpacked(&left), expected, "{:?}, a={}, b={}", W::ONE, a, b);
+            let right = left.split_off(b.min(a));
+            assert_eq!(unpacked(&left), expected[..b.min(a)], "split_off");
+            assert_eq!(unpacked(&right), expected[b.min(a)..], "split_off");
+        }
+    }
+}
+
+#[test]
+fn word_sizes() {
+    check_word_size::<u8>();
+    check_word_size::<u16>();
+    check_word_size::<u32>();
+    check_word_size::<u64>();
+    check_word_size::<u128>();
+    check_word_size::<usize>();
+}
@@ -0,0 +1,66 @@
+// Checks the thread pool returns every result and survives panicking jobs.
+
+use parallel_primes::pool::{Executor, ThreadPool};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn results_in_submission_order() {
+    let pool = ThreadPool::new(3);
+    assert_eq!(pool.threads(), 3);
+    let tasks: Vec<_> = (0..100u64).map(|i| pool.submit(move || i * i)).collect();
+    let results: Vec<u64> = tasks.into_iter().map(|task| task.join()).collect();
+    assert_eq!(results, (0..100).map(|i| i * i).collect::<Vec<_>>());
+}
+
+#[test]
+fn workers_are_reused() {
+    let pool = ThreadPool::new(2);
+    let tasks: Vec<_> = (0..50)
+        .map(|_| pool.submit(|| thread::current().id()))
+        .collect();
+    let mut ids: Vec<_> = tasks.into_iter().map(|task| task.join()).collect();
+    ids.sort_unstable_by_key(|id| format!("{:?}", id));
+    ids.dedup();
+    assert!(ids.len() <= 2, "{} threads", ids.len());
+}
+
+#[test]
+fn panic_is_resumed_in_join() {
+    let pool = ThreadPool::new(1);
+    let task = pool.submit(|| -> u32 { panic!("job failed") });
+    let result = panic::catch_unwind(AssertUnwindSafe(|| task.join()));
+    assert!(result.is_err());
+    // the single worker is still alive
+    assert_eq!(pool.submit(|| 7).join(), 7);
+}
+
+// a single test, so nothing else holds on to the shared pool while it is replaced
+#[test]
+fn shared_pool() {
+    for executor in [Executor::Spawn, Executor::Pool] {
+        let workers = executor.workers(4);
+        let data = Arc::new(vec![1, 2, 3]);
+        let tasks: Vec<_> = (0..4)
+            .map(|i| {
+                let data = data.clone();
+                workers.submit(move || data.iter().sum::<i32>() * i)
+            })
+            .collect();
+        let sums: Vec<i32> = tasks.into_iter().map(|task| task.join()).collect();
+        assert_eq!(sums, [0, 6, 12, 18]);
+    }
+
+    let pool = ThreadPool::shared(5);
+    assert!(pool.threads() >= 5);
+    assert!(Arc::ptr_eq(&pool, &ThreadPool::shared(3)));
+    assert!(ThreadPool::shared(0).threads() >= 1);
+    // a larger request replaces the pool, the old one shuts down with its last user
+    let old = Arc::downgrade(&pool);
+    let larger = ThreadPool::shared(pool.threads() + 1);
+    assert_eq!(larger.threads(), pool.threads() + 1);
+    drop(pool);
+    assert!(old.upgrade().is_none());
+    assert!(Arc::ptr_eq(&larger, &ThreadPool::shared(1)));
+}
@@ -0,0 +1,167 @@
+// Checks the Miller–Rabin test against the sieves and against trial division in windows of
+// large numbers.
+
+use parallel_primes::bigint::BigUint;
+use parallel_primes::primality::{
+    is_prime_bpsw, is_prime_u64, is_strong_lucas_prp, mul_mod, pow_mod,
+};
+use parallel_primes::sieve::packed_sieve;
+use parallel_primes::trial_division::prime_slice;
+
+#[test]
+fn matches_sieve() {
+    let max = 1 << 20;
+    let sieve = packed_sieve(max);
+    for n in 0..=max {
+        assert_eq!(is_prime_u64(n), sieve.is_set(n as usize), "{}", n);
+    }
+}
+
+#[test]
+fn matches_trial_division_in_windows() {
+    // the primes up to 2^22, enough for trial division of any window below 2^44
+    let small_primes: Vec<u64> = packed_sieve(1 << 22)
+        .iter_ones()
+        .map(|p| p as u64)
+        .collect();
+    for start in [(1u64 << 32) - 10_001, 1_000_000_000_001, (1 << 44) - 20_001] {
+        let end = start + 20_000;
+        let expected = prime_slice(&small_primes, start, end);
+        let found: Vec<u64> = (start..end)
+            .step_by(2)
+            .filter(|&n| is_prime_u64(n))
+            .collect();
+        assert_eq!(found, expected, "window at {}", start);
+    }
+}
+
+#[test]
+fn pseudoprimes_and_large_primes() {
+    let composites = [
+        561,                       // Carmichael
+        2_047,                     // strong pseudoprime to base 2
+        1_373_653,                 // bases 2, 3
+        25_326_001,                // bases 2, 3, 5
+        3_215_031_751,             // bases 2, 3, 5, 7
+        2_152_302_898_747,         // bases 2 .. 11
+        3_474_749_660_383,         // bases 2 .. 13
+        341_550_071_728_321,       // bases 2 .. 17
+        3_825_123_056_546_413_051, // bases 2 .. 23
+        4_294_967_297,             // 2^32 + 1 = 641 * 6700417
+        18_446_744_073_709_551_615,
+        4_294_967_291 * 4_294_967_279,
+    ];
+    for n in composites {
+        assert!(!is_prime_u64(n), "{}", n);
+    }
+    let primes = [
+        4_294_967_291,
+        1_000_000_000_039,
+        9_223_372_036_854_775_783,
+        18_446_744_073_709_551_557,
+    ];
+    for n in primes {
+        assert!(is_prime_u64(n), "{}", n);
+    }
+}
+
+#[test]
+fn modular_arithmetic() {
+    let m = u64::MAX - 58;
+    assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+    assert_eq!(pow_mod(2, 10, 1000), 24);
+    assert_eq!(pow_mod(5, 0, 1), 0);
+    // Fermat's little theorem
+    assert_eq!(pow_mod(3, m - 1, m), 1);
+}
+
+#[test]
+fn bpsw_on_large_numbers() {
+    let one = BigUint::one();
+    let mersenne = |p: usize| &(&one << p) - &one;
+    // Mersenne primes of up to 1332 digits
+    for p in [61, 89, 107, 127, 521, 607, 1279, 2203, 4423] {
+        assert!(is_prime_bpsw(&mersenne(p)), "2^{} - 1", p);
+    }
+    // 2^67 - 1 = 193707721 * 761838257287, the Fermat number 2^128 + 1 and products of primes
+    assert!(!is_prime_bpsw(&mersenne(67)));
+    assert!(!is_prime_bpsw(&(&(&one << 128) + &one)));
+    assert!(!is_prime_bpsw(&(&mersenne(89) * &mersenne(107))));
+    assert!(!is_prime_bpsw(&(&mersenne(127) * &mersenne(127))));
+    let prime: BigUint = "170141183460469231731687303715884105727".parse().unwrap();
+    assert_eq!(prime, mersenne(127));
+    // the first primes above 2^64 and 10^30
+    let above_2_64 = &(&one << 64) + &BigUint::from(13u64);
+    assert!(is_prime_bpsw(&above_2_64));
+    let above_10_30: BigUint = "1000000000000000000000000000057".parse().unwrap();
+    assert!(is_prime_bpsw(&above_10_30));
+    for i in 1..57 {
+        let n: BigUint = format!("1{:030}", i).parse().unwrap();
+        assert!(!is_prime_bpsw(&n), "{}", n);
+    }
+}
+
+// the strong probable prime test to base 2 of the BPSW test, for odd n
+fn base2_sprp(n: u64) -> bool {
+    let s = (n - 1).trailing_zeros();
+    let mut x = pow_mod(2, (n - 1) >> s, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+#[test]
+fn bpsw_has_no_u64_pseudoprimes() {
+    // is_prime_bpsw() hands u64s to is_prime_u64(), so run its two tests by hand
+    let odd = (15..100_000u64)
+        .chain(1 << 40..(1 << 40) + 3000)
+        .chain(u64::MAX - 3000..=u64::MAX)
+        .filter(|n| n % 2 == 1);
+    for n in odd {
+        let passes = base2_sprp(n) && is_strong_lucas_prp(&BigUint::from(n));
+        assert_eq!(passes, is_prime_u64(n), "{}", n);
+    }
+}
+
+#[test]
+fn bpsw_above_u64() {
+    // products of two primes above the trial division limit, so the composites reach both tests
+    let primes = |from: u64| {
+        (from..)
+            .filter(|&n| is_prime_u64(n))
+            .take(5)
+            .collect::<Vec<_>>()
+    };
+    for p in primes(1 << 33) {
+        let p = BigUint::from(p);
+        assert!(!is_prime_bpsw(&(&p * &p)), "{}^2", p);
+        for q in primes(1 << 40).into_iter().chain(primes(u64::MAX - 1000)) {
+            let n = &p * &BigUint::from(q);
+            assert!(!is_prime_bpsw(&n), "{}", n);
+        }
+    }
+}
+
+#[test]
+fn strong_lucas_pseudoprimes() {
+    // OEIS A217255, the composites passing the strong Lucas test below 10^5
+    let expected = [
+        5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519, 75077, 97439,
+    ];
+    let found: Vec<u64> = (15..100_000u64)
+        .step_by(2)
+        .filter(|&n| !is_prime_u64(n) && is_strong_lucas_prp(&BigUint::from(n)))
+        .collect();
+    assert_eq!(found, expected);
+    // and every odd prime above 5 passes
+    for p in (7..20_000u64).step_by(2).filter(|&n| is_prime_u64(n)) {
+        assert!(is_strong_lucas_prp(&BigUint::from(p)), "{}", p);
+    }
+}
@@ -0,0 +1,50 @@
+// Checks the prime count oracle behind --verify against its table and against a sieve.
+
+use parallel_primes::reference::{count_below, known, prime_pi};
+use parallel_primes::sieve::odd_sieve;
+
+#[test]
+fn prime_pi_matches_table() {
+    // the entries up to 1e10, the larger ones take too long without optimizations
+    let powers = (0..=10).map(|e| 10u64.pow(e));
+    let threes = (2..=9).map(|e| 3 * 10u64.pow(e));
+    for max in powers.chain(threes).chain([1 << 32]) {
+        let count = known(max).unwrap_or_else(|| panic!("{} not in the table", max));
+        assert_eq!(prime_pi(max - 1), count, "max={}", max);
+    }
+}
+
+#[test]
+fn prime_pi_matches_sieve() {
+    let max = 5000;
+    let odd = odd_sieve(max);
+    let mut count = 0;
+    for n in 0..=max {
+        // odd_sieve() has 1 unset and 2 missing
+        if n == 2 || (n % 2 == 1 && odd.is_set(n as usize / 2)) {
+            count += 1;
+        }
+        assert_eq!(prime_pi(n), count, "n={}", n);
+    }
+}
+
+#[test]
+fn count_below_uses_table() {
+    // prime_pi() would take hours for these
+    assert_eq!(count_below(u64::MAX), 425_656_284_035_217_743);
+    assert_eq!(
+        count_below(10_000_000_000_000_000_000),
+        known(10_000_000_000_000_000_000).unwrap()
+    );
+    // and falls back to prime_pi() for the other limits
+    for max in [0, 2, 3, 12_345, 1_000_003] {
+        assert_eq!(known(max), None);
+        assert_eq!(
+            count_below(max),
+            prime_pi(max.saturating_sub(1)),
+            "max={}",
+            max
+        );
+    }
+    assert_eq!(count_below(12_345), 1_474);
+}
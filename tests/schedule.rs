@@ -0,0 +1,72 @@
+// Checks both schedules run every slice exactly once and account for all of them in the load.
+
+use parallel_primes::pool::Executor;
+use parallel_primes::schedule::{self, Schedule};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn every_slice_once_in_order() {
+    for schedule in [
+        Schedule::Static,
+        Schedule::Dynamic { chunk: 1 },
+        Schedule::Dynamic { chunk: 7 },
+        Schedule::Dynamic { chunk: 1000 },
+    ] {
+        for (slices, threads) in [(0, 4), (1, 4), (3, 8), (100, 3), (101, 1)] {
+            let (results, load) =
+                schedule::run(schedule, Executor::Pool, slices, threads, || (), |_, i| i);
+            assert_eq!(results, (0..slices).collect::<Vec<_>>(), "{:?}", schedule);
+            assert_eq!(load.threads.len(), threads.clamp(1, slices.max(1)));
+            let counted: usize = load.threads.iter().map(|t| t.slices).sum();
+            assert_eq!(counted, slices, "{:?}", schedule);
+            for t in &load.threads {
+                assert!(t.busy + t.idle <= load.wall + load.wall / 100, "{:?}", t);
+            }
+        }
+    }
+}
+
+#[test]
+fn state_is_per_thread() {
+    // each thread counts its slices in its own state
+    let (results, load) = schedule::run(
+        Schedule::Static,
+        Executor::Pool,
+        10,
+        2,
+        || 0,
+        |seen: &mut usize, _| {
+            *seen += 1;
+            *seen
+        },
+    );
+    assert_eq!(results, [1, 1, 2, 2, 3, 3, 4, 4, 5, 5]);
+    assert_eq!(load.threads[0].slices, 5);
+}
+
+#[test]
+fn dynamic_balances_uneven_slices() {
+    // the first slice takes long, with the dynamic schedule the other thread takes over the
+    // slices the static one keeps for the waiting thread, sleeping doesn't need a second core
+    let cost = |_: &mut (), i: usize| {
+        if i == 0 {
+            thread::sleep(Duration::from_millis(200));
+        }
+        i
+    };
+    let (fixed, fixed_load) = schedule::run(Schedule::Static, Executor::Pool, 40, 2, || (), cost);
+    let (dynamic, dynamic_load) = schedule::run(
+        Schedule::Dynamic { chunk: 1 },
+        Executor::Pool,
+        40,
+        2,
+        || (),
+        cost,
+    );
+    assert_eq!(fixed, dynamic);
+    assert_eq!(fixed_load.threads[0].slices, 20);
+    assert_eq!(fixed_load.threads[1].slices, 20);
+    let most = dynamic_load.threads.iter().map(|t| t.slices).max();
+    assert!(most > Some(20), "{}", dynamic_load);
+}
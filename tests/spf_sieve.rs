@@ -0,0 +1,54 @@
+// Checks the smallest prime factor tables against trial division and against each other.
+
+use parallel_primes::factor::factor;
+use parallel_primes::sieve::packed_sieve;
+use parallel_primes::spf_sieve::SpfSieve;
+
+fn smallest_factor(n: u32) -> u32 {
+    if n < 2 {
+        return 0;
+    }
+    (2..)
+        .take_while(|p| p * p <= n)
+        .find(|&p| n.is_multiple_of(p))
+        .unwrap_or(n)
+}
+
+#[test]
+fn matches_trial_division() {
+    let limit = 200_000;
+    let sieve = SpfSieve::new(limit);
+    assert_eq!(sieve.limit(), limit);
+    let primes = packed_sieve(limit as u64);
+    for n in 0..=limit {
+        assert_eq!(sieve.smallest_factor(n), smallest_factor(n), "{}", n);
+        assert_eq!(sieve.is_prime(n), primes.is_set(n as usize), "{}", n);
+        let expected: Vec<(u32, u32)> = factor(n as u64)
+            .into_iter()
+            .map(|(p, exponent)| (p as u32, exponent))
+            .collect();
+        assert_eq!(sieve.factor(n), expected, "{}", n);
+    }
+    assert_eq!(sieve.primes().count(), 17984);
+}
+
+#[test]
+fn threaded_matches_linear() {
+    for limit in [0, 1, 2, 3, 100, 65_535, 65_536, 65_537, 1_000_003] {
+        let linear = SpfSieve::new(limit);
+        for threads in [1, 3, 8] {
+            let segmented = SpfSieve::with_threads(limit, threads);
+            assert_eq!(segmented.limit(), limit);
+            for n in 0..=limit {
+                assert_eq!(
+                    segmented.smallest_factor(n),
+                    linear.smallest_factor(n),
+                    "{} with limit {}, {} threads",
+                    n,
+                    limit,
+                    threads
+                );
+            }
+        }
+    }
+}
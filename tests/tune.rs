@@ -0,0 +1,36 @@
+// Checks the tuning grid caps the page sizes at the L2 cache in the unit of each algorithm.
+
+use parallel_primes::counter;
+use parallel_primes::hw::CacheSizes;
+use parallel_primes::tune::TuneSpace;
+
+#[test]
+fn page_sizes_capped_at_l2() {
+    let caches = CacheSizes {
+        l2: Some(64 * 1024),
+        ..CacheSizes::default()
+    };
+    let bytes = TuneSpace::for_machine(4, caches, 8);
+    assert_eq!(bytes.max_page_size, 64 * 1024);
+    assert_eq!(bytes.page_sizes.last(), Some(&(64 * 1024)));
+    // 64K bytes of packed bits are 512K numbers
+    let bits = TuneSpace::for_machine(4, caches, 1);
+    assert_eq!(bits.max_page_size, 512 * 1024);
+    assert_eq!(bits.page_sizes.last(), Some(&(512 * 1024)));
+    assert_eq!(bits.page_sizes.first(), Some(&256));
+}
+
+#[test]
+fn page_size_units() {
+    for (algo, bits) in [
+        ("p16", 8),
+        ("p17", 1),
+        ("p17-u8", 1),
+        ("p18", 1),
+        ("p19", 1),
+        ("p20", 8),
+    ] {
+        let counter = counter::by_name(algo).unwrap();
+        assert_eq!(counter.page_size_bits(), bits, "{}", algo);
+    }
+}